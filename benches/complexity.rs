@@ -0,0 +1,27 @@
+use std::fs;
+use std::path::PathBuf;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use cyclo::file_parser::{self, ParserOptions};
+
+
+/// Fixtures representative of the languages, from brace tracking to layout
+const FIXTURES: [&str; 5] = ["sample.c", "sample.cpp", "sample.py", "sample.js", "sample.hs"];
+
+/// Benchmark estimating the complexity of each fixture, repeated so the
+/// input is big enough to measure
+fn complexity(c: &mut Criterion)
+{
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let options = ParserOptions::default();
+
+    for name in FIXTURES
+    {
+        let source = fs::read_to_string(fixtures.join(name)).unwrap().repeat(100);
+
+        c.bench_function(&format!("complexity {}", name), |b| b.iter(|| file_parser::source_complexity(name, &source, &options)));
+    }
+}
+
+criterion_group!(benches, complexity);
+criterion_main!(benches);
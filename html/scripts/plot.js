@@ -2,3 +2,20 @@ const element = document.getElementById("plot");
 
 // jsondata is from plot.js
 Plotly.newPlot(element, jsondata)
+
+// snippets is from cyclo.js too, and is only set when cyclo was run with --snippets
+const snippetsElement = document.getElementById("snippets");
+
+if (typeof snippets !== "undefined")
+{
+    for (const snippet of snippets)
+    {
+        const title = document.createElement("h2");
+        title.textContent = `${snippet.label}:${snippet.start}-${snippet.end} (cc: ${snippet.cc})`;
+
+        const source = document.createElement("pre");
+        source.textContent = snippet.source;
+
+        snippetsElement.append(title, source);
+    }
+}
@@ -0,0 +1,189 @@
+use clap::ValueEnum;
+
+use crate::file_parser::{self, ParserOptions};
+use crate::halstead;
+use crate::layout::LayoutTracker;
+
+
+/// The algorithms the complexity of a file can be estimated with
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm
+{
+    /// Count the decision statements, logical operations and labels
+    #[default]
+    DecisionCount,
+    /// The decision count plus one for each function, which is the sum of
+    /// the cyclomatic complexities of the functions
+    Mccabe,
+    /// Weight each decision statement by how deeply it is nested, which is
+    /// estimated from its indentation
+    Cognitive,
+    /// The Halstead difficulty of the file
+    Halstead,
+}
+
+impl Algorithm
+{
+    /// Get the implementation of the algorithm
+    pub fn implementation(self) -> Box<dyn ComplexityAlgorithm>
+    {
+        match self
+        {
+            Algorithm::DecisionCount => Box::new(DecisionCount),
+            Algorithm::Mccabe => Box::new(McCabe),
+            Algorithm::Cognitive => Box::new(Cognitive),
+            Algorithm::Halstead => Box::new(HalsteadDifficulty),
+        }
+    }
+}
+
+/// A way of estimating the complexity of the contents of a file
+pub trait ComplexityAlgorithm: Send + Sync
+{
+    /// Get the complexity and number of functions of the contents of a file,
+    /// or None if the language isn't supported
+    fn complexity(&self, filename: &str, content: &str, options: &ParserOptions) -> Option<(f64, u64)>;
+}
+
+/// The decision statements, logical operations and labels counted by the
+/// keyword matcher
+pub struct DecisionCount;
+
+impl ComplexityAlgorithm for DecisionCount
+{
+    fn complexity(&self, filename: &str, content: &str, options: &ParserOptions) -> Option<(f64, u64)>
+    {
+        file_parser::source_complexity(filename, content, options)
+    }
+}
+
+/// Each function has a complexity of one more than its decisions, and code
+/// outside of any function is one path through the file
+pub struct McCabe;
+
+impl ComplexityAlgorithm for McCabe
+{
+    fn complexity(&self, filename: &str, content: &str, options: &ParserOptions) -> Option<(f64, u64)>
+    {
+        /* data files have nothing to analyze */
+        if file_parser::is_data_file(filename)
+        {
+            return Some((0.0, 0));
+        }
+
+        let (cc, functions) = file_parser::source_complexity(filename, content, options)?;
+
+        Some((cc + functions.max(1) as f64, functions))
+    }
+}
+
+/// Each decision statement costs one more for every decision it is nested
+/// in, and each logical operation costs one. A statement is nested in the
+/// decisions above it which are indented less, so it's only an estimate for
+/// code which isn't indented consistently. Labels, like the cases of a
+/// switch, don't add to the cost of the switch
+pub struct Cognitive;
+
+impl Cognitive
+{
+    /// Get the cognitive complexity of the contents of a file, or None if
+    /// the language isn't supported
+    fn cost(filename: &str, content: &str, options: &ParserOptions) -> Option<f64>
+    {
+        if file_parser::is_data_file(filename)
+        {
+            return Some(0.0);
+        }
+
+        if file_parser::is_markdown(filename)
+        {
+            let cost = file_parser::fenced_blocks(content, options).iter()
+                                                                   .filter_map(|(block, source)| Cognitive::cost(block, source, options))
+                                                                   .sum();
+
+            return Some(cost);
+        }
+
+        let keywords = file_parser::keywords_for(filename, options)?;
+        let mut nesting: Vec<usize> = Vec::new();
+        let mut layout = LayoutTracker::new();
+        let mut cost = 0;
+
+        for line in file_parser::stripped_source(filename, content, options)?
+        {
+            let line = match keywords.case_insensitive
+            {
+                true => line.to_lowercase(),
+                false => line,
+            };
+
+            /* like the keyword matcher, a | in a Haskell data declaration
+             * isn't a guard and the arrow of a lambda isn't an alternative */
+            let line = match keywords.layout_functions
+            {
+                true => {
+                    layout.feed(&line);
+                    file_parser::blank_lambda_arrows(&line)
+                },
+                false => line,
+            };
+            let code = line.trim_start();
+
+            if code.is_empty()
+            {
+                continue;
+            }
+
+            /* the decisions indented as much or more than this line have
+             * ended, like the if before an else */
+            let indent = line.len() - code.len();
+            while nesting.last().is_some_and(|&open| open >= indent)
+            {
+                nesting.pop();
+            }
+
+            cost += keywords.logical_ops.iter()
+                                        .map(|n| file_parser::count_logical_op(&line, n, keywords.word_boundaries))
+                                        .sum::<usize>();
+
+            let is_decision = keywords.non_statements.iter().all(|n| !line.contains(*n))
+                              && (keywords.statements.iter().any(|n| file_parser::contains_keyword(&line, n, keywords.word_boundaries))
+                                  || (!layout.in_data() && keywords.line_statements.iter().any(|n| code.starts_with(*n))));
+
+            if is_decision
+            {
+                cost += 1 + nesting.len();
+                nesting.push(indent);
+            }
+        }
+
+        Some(cost as f64)
+    }
+}
+
+impl ComplexityAlgorithm for Cognitive
+{
+    fn complexity(&self, filename: &str, content: &str, options: &ParserOptions) -> Option<(f64, u64)>
+    {
+        let (_, functions) = file_parser::source_complexity(filename, content, options)?;
+
+        Some((Cognitive::cost(filename, content, options)?, functions))
+    }
+}
+
+/// The Halstead difficulty, which grows with the number of different
+/// operators and how often the operands are reused
+pub struct HalsteadDifficulty;
+
+impl ComplexityAlgorithm for HalsteadDifficulty
+{
+    fn complexity(&self, filename: &str, content: &str, options: &ParserOptions) -> Option<(f64, u64)>
+    {
+        let (_, functions) = file_parser::source_complexity(filename, content, options)?;
+
+        /* data files and Markdown have no operators of their own */
+        let difficulty = halstead::source_halstead(filename, content, options).map_or(0.0, |h| h.difficulty());
+
+        Some((difficulty, functions))
+    }
+}
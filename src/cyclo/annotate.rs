@@ -0,0 +1,93 @@
+use std::fs;
+use std::io;
+
+use crate::file_parser::{self, FileResult, ParserOptions};
+
+
+/// Marks a comment as an annotation written by cyclo, so annotating again
+/// replaces it rather than adding another
+const MARKER: &str = "cyclo:";
+
+/// Get the comment noting the complexity of a file, like
+/// `// cyclo: cc=23, nloc=410`, in the line comment syntax of its language.
+/// Components have no line comment which is valid at the top of the file, so
+/// they aren't annotated
+pub fn annotation(file: &FileResult, options: &ParserOptions) -> Option<String>
+{
+    let name = file_parser::parsed_name(&file.path, options);
+
+    if file_parser::is_component(&name)
+    {
+        return None;
+    }
+
+    let keywords = file_parser::keywords_for(&name, options)?;
+    let comment = keywords.comments.first()?;
+
+    Some(format!("{} {} cc={}, nloc={}", comment, MARKER, file.cc, file.nloc))
+}
+
+/// Add an annotation to the top of the contents of a file, after any shebang
+/// line. An annotation left by an earlier run is replaced. The lines keep
+/// their endings, and the annotation ends like the file's lines do
+pub fn annotate(content: &str, annotation: &str) -> String
+{
+    let comment = annotation.split_whitespace().next().unwrap();
+    let newline = match content.contains("\r\n")
+    {
+        true => "\r\n",
+        false => "\n",
+    };
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+    let index = match lines.first()
+    {
+        Some(line) if line.starts_with("#!") => 1,
+        _ => 0,
+    };
+
+    /* a shebang which is the whole file needs a line ending before the
+     * annotation can go after it */
+    if index == 1 && !lines[0].ends_with('\n')
+    {
+        lines[0].push_str(newline);
+    }
+
+    let existing = lines.get(index)
+                        .and_then(|line| line.strip_prefix(comment))
+                        .is_some_and(|rest| rest.trim_start().starts_with(MARKER));
+
+    if existing
+    {
+        let ending = &lines[index][lines[index].trim_end_matches(['\r', '\n']).len()..];
+        lines[index] = format!("{}{}", annotation, ending);
+    }
+    else
+    {
+        /* like the last line, the annotation only ends with a newline if
+         * the file does */
+        let ending = match index < lines.len() || content.is_empty()
+        {
+            true => newline,
+            false => "",
+        };
+        lines.insert(index, format!("{}{}", annotation, ending));
+    }
+
+    lines.concat()
+}
+
+/// Write the annotation to the top of a file, returning the annotation or
+/// None if the file can't be annotated
+pub fn annotate_file(file: &FileResult, options: &ParserOptions) -> io::Result<Option<String>>
+{
+    let annotation = match annotation(file, options)
+    {
+        Some(annotation) => annotation,
+        None => return Ok(None),
+    };
+
+    let content = fs::read_to_string(&file.path)?;
+    fs::write(&file.path, annotate(&content, &annotation))?;
+
+    Ok(Some(annotation))
+}
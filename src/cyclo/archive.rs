@@ -0,0 +1,198 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use flate2::read::GzDecoder;
+use snafu::prelude::*;
+
+use crate::exclude::{ExcludePatterns, IGNORE_FILE};
+use crate::file_parser::{self, FileResult, ParserOptions};
+
+
+/// Errors reading an archive
+#[derive(Debug, Snafu)]
+pub enum ArchiveError
+{
+    #[snafu(display("Unable to read the archive '{}': {source}", path.display()))]
+    ReadArchive { path: PathBuf, source: io::Error },
+
+    #[snafu(display("Unable to read the zip archive '{}': {source}", path.display()))]
+    ReadZip { path: PathBuf, source: zip::result::ZipError },
+}
+
+/// Check if a path is an archive which can be analyzed without extracting it
+pub fn is_archive(path: &Path) -> bool
+{
+    let name = path.to_string_lossy();
+    let extensions = [".tar.gz", ".tgz", ".tar", ".zip"];
+
+    extensions.iter()
+              .any(|n| name.ends_with(*n))
+}
+
+/// Check if an entry is in a hidden directory or is a hidden file, unless
+/// the directory is unhidden
+fn is_hidden_entry(name: &str, unhide: &[String]) -> bool
+{
+    let mut components = name.split('/').filter(|c| !c.is_empty()).peekable();
+
+    while let Some(component) = components.next()
+    {
+        let is_dir = components.peek().is_some();
+
+        if component.starts_with('.') && component != "." && !(is_dir && unhide.iter().any(|u| u == component))
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Parse an archive entry, if it should be analyzed with the options and
+/// isn't ignored
+fn parse_entry(archive: &Path, name: &str, content: &[u8], ignore: &ExcludePatterns, options: &ParserOptions) -> Option<FileResult>
+{
+    let name = name.trim_start_matches("./");
+    let filename = name.rsplit('/').next().unwrap();
+
+    if is_hidden_entry(name, &options.unhide)
+        || !file_parser::is_file_valid(filename, options)
+        || options.exclude.is_excluded(Path::new(name), false)
+        || ignore.is_excluded(Path::new(name), false)
+    {
+        return None;
+    }
+
+    let content = String::from_utf8_lossy(content);
+    let (cc, functions, nloc) = file_parser::parse_source(filename, &content, options)?;
+
+    /* the archive is the root, like the directory is for a walk */
+    let root = archive.file_name().unwrap().to_string_lossy();
+    let label = format!("{}/{}", root, name);
+    let parent = label.rsplit_once('/').unwrap().0.to_string();
+
+    let language = file_parser::language_name(filename).to_string();
+
+    Some(FileResult { path: archive.join(name), label, parent, nloc, cc, functions, language })
+}
+
+/// Read each file in a tar, gzipped tar, or zip archive in memory, in the
+/// order they're stored, until `visit` returns false for one
+fn for_each_entry<F>(path: &Path, mut visit: F) -> Result<(), ArchiveError>
+where
+    F: FnMut(&str, &[u8]) -> bool,
+{
+    let file = File::open(path).context(ReadArchiveSnafu { path })?;
+    let name = path.to_string_lossy();
+
+    if name.ends_with(".zip")
+    {
+        let mut zip = zip::ZipArchive::new(file).context(ReadZipSnafu { path })?;
+
+        for i in 0..zip.len()
+        {
+            let mut entry = zip.by_index(i).context(ReadZipSnafu { path })?;
+
+            if !entry.is_file()
+            {
+                continue;
+            }
+
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).context(ReadArchiveSnafu { path })?;
+
+            if !visit(entry.name(), &content)
+            {
+                break;
+            }
+        }
+    }
+    else
+    {
+        let reader: Box<dyn Read> = if name.ends_with(".tar")
+        {
+            Box::new(file)
+        }
+        else
+        {
+            Box::new(GzDecoder::new(file))
+        };
+
+        let mut tar = tar::Archive::new(reader);
+
+        for entry in tar.entries().context(ReadArchiveSnafu { path })?
+        {
+            let mut entry = entry.context(ReadArchiveSnafu { path })?;
+
+            if !entry.header().entry_type().is_file()
+            {
+                continue;
+            }
+
+            let entry_name = entry.path().context(ReadArchiveSnafu { path })?.to_string_lossy().to_string();
+
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).context(ReadArchiveSnafu { path })?;
+
+            if !visit(&entry_name, &content)
+            {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the patterns in the .cycloignore at the root of an archive, which
+/// are empty if it doesn't have one
+fn ignore_patterns(path: &Path) -> Result<ExcludePatterns, ArchiveError>
+{
+    let mut ignore = ExcludePatterns::default();
+
+    for_each_entry(path, |name, content|
+    {
+        if name.trim_start_matches("./") != IGNORE_FILE
+        {
+            return true;
+        }
+
+        ignore = ExcludePatterns::from_ignore_content(&String::from_utf8_lossy(content));
+        false
+    })?;
+
+    Ok(ignore)
+}
+
+/// Analyze every valid file in a tar, gzipped tar, or zip archive, reading
+/// the entries in memory, up to the maximum number of files. The entries
+/// ignored by a .cycloignore at the root of the archive are skipped, like
+/// they are in a directory. The results are sorted by label
+pub fn analyze_archive(path: &Path, options: &ParserOptions) -> Result<Vec<FileResult>, ArchiveError>
+{
+    /* the ignore file can be anywhere in the archive, so it's found first */
+    let ignore = ignore_patterns(path)?;
+    let mut files = Vec::new();
+
+    for_each_entry(path, |name, content|
+    {
+        let file = match parse_entry(path, name, content, &ignore, options)
+        {
+            Some(file) => file,
+            None => return true,
+        };
+
+        /* only the files which are analyzed count toward the limit */
+        if options.max_files.as_ref().is_some_and(|l| !l.claim())
+        {
+            return false;
+        }
+
+        files.push(file);
+        true
+    })?;
+
+    files.sort_by(|a, b| a.label.cmp(&b.label));
+
+    Ok(files)
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use snafu::prelude::*;
+
+use crate::file_parser::FileResult;
+use crate::output::{JsonReport, JSON_SCHEMA_VERSION};
+
+
+/// Errors reading or writing a baseline
+#[derive(Debug, Snafu)]
+pub enum BaselineError
+{
+    #[snafu(display("Unable to read the baseline '{}': {source}", path.display()))]
+    ReadBaseline { path: PathBuf, source: std::io::Error },
+
+    #[snafu(display("Unable to write the baseline '{}': {source}", path.display()))]
+    WriteBaseline { path: PathBuf, source: std::io::Error },
+
+    #[snafu(display("The baseline '{}' is not valid JSON output: {source}", path.display()))]
+    ParseBaseline { path: PathBuf, source: serde_json::Error },
+
+    #[snafu(display("The baseline '{}' has schema version {version}, but version {JSON_SCHEMA_VERSION} is needed", path.display()))]
+    BaselineVersion { path: PathBuf, version: u32 },
+}
+
+/// A file whose complexity went up since the baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression
+{
+    pub label: String,
+    /// Complexity in the baseline, or None if the file is new
+    pub before: Option<f64>,
+    /// Complexity now
+    pub after: f64,
+}
+
+/// Write the analysis to a baseline, which is the same versioned JSON as
+/// --format json
+pub fn write_baseline(path: &Path, files: &[FileResult]) -> Result<(), BaselineError>
+{
+    let report = serde_json::to_string(&JsonReport::new(files, &[])).unwrap();
+
+    fs::write(path, report).context(WriteBaselineSnafu { path })
+}
+
+/// Read a baseline, rejecting one written with a different schema version
+pub fn read_baseline(path: &Path) -> Result<JsonReport, BaselineError>
+{
+    let content = fs::read_to_string(path).context(ReadBaselineSnafu { path })?;
+    let report: JsonReport = serde_json::from_str(&content).context(ParseBaselineSnafu { path })?;
+
+    ensure!(report.schema_version == JSON_SCHEMA_VERSION, BaselineVersionSnafu { path, version: report.schema_version });
+
+    Ok(report)
+}
+
+/// Get the files whose complexity is higher than in the baseline, including
+/// new files with any complexity
+pub fn regressions(baseline: &JsonReport, files: &[FileResult]) -> Vec<Regression>
+{
+    let before: HashMap<&str, f64> = baseline.files.iter()
+                                                   .map(|f| (f.label.as_str(), f.cc))
+                                                   .collect();
+
+    files.iter()
+         .filter_map(|f|
+         {
+             let old = before.get(f.label.as_str()).copied();
+
+             match old
+             {
+                 Some(cc) if f.cc <= cc => None,
+                 None if f.cc == 0.0 => None,
+                 _ => Some(Regression { label: f.label.clone(), before: old, after: f.cc }),
+             }
+         })
+         .collect()
+}
+
+/// Get the files already in the baseline whose complexity rose by more than
+/// the tolerance. New files aren't included, they only count towards the
+/// total
+pub fn increases(baseline: &JsonReport, files: &[FileResult], tolerance: f64) -> Vec<Regression>
+{
+    let before: HashMap<&str, f64> = baseline.files.iter()
+                                                   .map(|f| (f.label.as_str(), f.cc))
+                                                   .collect();
+
+    files.iter()
+         .filter_map(|f|
+         {
+             let cc = *before.get(f.label.as_str())?;
+
+             (f.cc - cc > tolerance).then(|| Regression { label: f.label.clone(), before: Some(cc), after: f.cc })
+         })
+         .collect()
+}
+
+/// Get the total complexity in the baseline and now, if it rose by more than
+/// the tolerance
+pub fn total_increase(baseline: &JsonReport, files: &[FileResult], tolerance: f64) -> Option<(f64, f64)>
+{
+    let before = baseline.files.iter().map(|f| f.cc).sum::<f64>();
+    let after = files.iter().map(|f| f.cc).sum::<f64>();
+
+    (after - before > tolerance).then_some((before, after))
+}
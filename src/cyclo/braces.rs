@@ -0,0 +1,168 @@
+/// The kind of block opened by a brace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block
+{
+    /// The body of a function
+    Function,
+    /// A namespace or extern "C" block, whose contents are still top level
+    Transparent,
+    /// Anything else, like a class body, initializer, or control statement
+    Other,
+}
+
+/// Tracks the depth of braces through the lines of a C/C++ file to detect
+/// function definitions. A function is a block opened outside of any other
+/// function whose preceding text looks like a signature, so a void function
+/// with no returns is counted and one with many returns is only counted once.
+/// Comments, string literals, and character literals are skipped so braces
+/// inside them aren't counted
+#[derive(Debug, Default)]
+pub struct BraceTracker
+{
+    /// The blocks currently open
+    blocks: Vec<Block>,
+    /// The text since the last statement or block boundary, which is the
+    /// signature if a block is opened
+    signature: String,
+    /// Whether a block comment is open
+    in_comment: bool,
+    /// Number of lines fed so far
+    line: usize,
+    /// Line the signature started on
+    signature_start: usize,
+    /// Line each open function started on
+    function_starts: Vec<usize>,
+    /// Number of functions detected
+    pub functions: u64,
+    /// First and last lines of each function closed so far, numbered from 1
+    pub spans: Vec<(usize, usize)>,
+}
+
+impl BraceTracker
+{
+    pub fn new() -> BraceTracker
+    {
+        BraceTracker::default()
+    }
+
+    /// Check if the text before a brace is a function signature rather than
+    /// a type, initializer, or namespace
+    fn is_signature(signature: &str) -> bool
+    {
+        let types = ["struct", "union", "enum", "class", "typedef"];
+
+        let before_paren = match signature.find('(')
+        {
+            Some(index) => &signature[..index],
+            None => return false,
+        };
+
+        signature.contains(')')
+            && !before_paren.contains('=')
+            && !types.iter().any(|t| signature.starts_with(t))
+    }
+
+    /// Open a block, working out what kind of block it is from the signature
+    fn open(&mut self)
+    {
+        let signature = self.signature.trim();
+        let in_function = self.blocks.contains(&Block::Function);
+
+        let block = if !in_function && (signature.starts_with("namespace") || signature.starts_with("extern \"C\""))
+        {
+            Block::Transparent
+        }
+        else if !in_function && BraceTracker::is_signature(signature)
+        {
+            self.functions += 1;
+            self.function_starts.push(self.signature_start);
+            Block::Function
+        }
+        else
+        {
+            Block::Other
+        };
+
+        self.blocks.push(block);
+        self.signature.clear();
+    }
+
+    /// Close the innermost block, recording the span if it was a function
+    fn close(&mut self)
+    {
+        if self.blocks.pop() == Some(Block::Function)
+        {
+            let start = self.function_starts.pop().unwrap();
+            self.spans.push((start, self.line));
+        }
+
+        self.signature.clear();
+    }
+
+    /// Feed the next line of the file to the tracker
+    pub fn feed(&mut self, line: &str)
+    {
+        self.line += 1;
+
+        /* preprocessor directives aren't part of any signature */
+        if !self.in_comment && line.trim_start().starts_with('#')
+        {
+            return;
+        }
+
+        let mut chars = line.chars().peekable();
+        let mut quote: Option<char> = None;
+
+        while let Some(c) = chars.next()
+        {
+            if self.in_comment
+            {
+                if c == '*' && chars.peek() == Some(&'/')
+                {
+                    chars.next();
+                    self.in_comment = false;
+                }
+
+                continue;
+            }
+
+            if let Some(q) = quote
+            {
+                if c == '\\'
+                {
+                    chars.next();
+                }
+                else if c == q
+                {
+                    quote = None;
+                }
+
+                continue;
+            }
+
+            match c
+            {
+                '"' | '\'' => quote = Some(c),
+                '/' if chars.peek() == Some(&'/') => break,
+                '/' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    self.in_comment = true;
+                },
+                '{' => self.open(),
+                '}' => self.close(),
+                ';' => self.signature.clear(),
+                _ => {
+                    /* the signature starts at its first visible character */
+                    if self.signature.trim().is_empty() && !c.is_whitespace()
+                    {
+                        self.signature_start = self.line;
+                    }
+
+                    self.signature.push(c);
+                },
+            }
+        }
+
+        self.signature.push(' ');
+    }
+}
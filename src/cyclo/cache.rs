@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+
+/// Marks the start of a cache file
+const MAGIC: &[u8; 8] = b"CYCLOCAC";
+
+/// The results cached for a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedResult
+{
+    pub nloc: u64,
+    pub cc: f64,
+    pub functions: u64,
+}
+
+/// Cache of file results keyed by the path and a hash of the contents, so
+/// files which haven't changed since the last run aren't parsed again. The
+/// cache is only valid for the version of cyclo and the options it was
+/// written with, and is thrown away otherwise. Only the files looked up in a
+/// run are saved, so deleted files drop out of the cache
+#[derive(Debug, Default)]
+pub struct Cache
+{
+    /// The version and options the results were computed with
+    fingerprint: String,
+    /// Results loaded from the cache file
+    old: HashMap<PathBuf, (u64, CachedResult)>,
+    /// Results looked up or computed in this run
+    new: Mutex<HashMap<PathBuf, (u64, CachedResult)>>,
+}
+
+/// Hash the contents of a file with 64 bit FNV-1a, which unlike the standard
+/// library's hasher is the same across runs and versions
+pub fn content_hash(content: &[u8]) -> u64
+{
+    content.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// Reads the fields of a cache file in order
+struct Reader<'a>
+{
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a>
+{
+    fn take(&mut self, len: usize) -> Option<&'a [u8]>
+    {
+        if self.bytes.len() < len
+        {
+            return None;
+        }
+
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        Some(taken)
+    }
+
+    fn u64(&mut self) -> Option<u64>
+    {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Option<String>
+    {
+        let len = self.u64()? as usize;
+
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
+
+/// Append a length prefixed string to a cache file
+fn write_string(bytes: &mut Vec<u8>, s: &str)
+{
+    bytes.extend((s.len() as u64).to_le_bytes());
+    bytes.extend(s.as_bytes());
+}
+
+impl Cache
+{
+    /// Load a cache file. The cache starts empty if the file doesn't exist,
+    /// can't be read, or was written with a different fingerprint
+    pub fn load(path: &Path, fingerprint: &str) -> Cache
+    {
+        let old = fs::read(path).ok()
+                                .and_then(|bytes| Cache::parse(&bytes, fingerprint))
+                                .unwrap_or_default();
+
+        Cache { fingerprint: fingerprint.to_string(), old, new: Mutex::new(HashMap::new()) }
+    }
+
+    /// Parse the entries of a cache file, or None if it isn't valid for the
+    /// fingerprint
+    fn parse(bytes: &[u8], fingerprint: &str) -> Option<HashMap<PathBuf, (u64, CachedResult)>>
+    {
+        let mut reader = Reader { bytes };
+
+        if reader.take(MAGIC.len())? != MAGIC || reader.string()? != fingerprint
+        {
+            return None;
+        }
+
+        let count = reader.u64()?;
+        let mut entries = HashMap::new();
+
+        for _ in 0..count
+        {
+            let path = PathBuf::from(reader.string()?);
+            let hash = reader.u64()?;
+            let result = CachedResult
+            {
+                nloc: reader.u64()?,
+                cc: f64::from_bits(reader.u64()?),
+                functions: reader.u64()?,
+            };
+
+            entries.insert(path, (hash, result));
+        }
+
+        Some(entries)
+    }
+
+    /// Get the cached result for a file, if its contents haven't changed
+    pub fn get(&self, path: &Path, hash: u64) -> Option<CachedResult>
+    {
+        let (cached_hash, result) = self.old.get(path)?;
+
+        if *cached_hash != hash
+        {
+            return None;
+        }
+
+        self.new.lock().unwrap().insert(path.to_path_buf(), (hash, *result));
+
+        Some(*result)
+    }
+
+    /// Cache the result for a file
+    pub fn insert(&self, path: &Path, hash: u64, result: CachedResult)
+    {
+        self.new.lock().unwrap().insert(path.to_path_buf(), (hash, result));
+    }
+
+    /// Write the results looked up or computed in this run to a cache file
+    pub fn save(&self, path: &Path) -> io::Result<()>
+    {
+        let new = self.new.lock().unwrap();
+        let mut bytes = Vec::new();
+
+        bytes.extend(MAGIC);
+        write_string(&mut bytes, &self.fingerprint);
+        bytes.extend((new.len() as u64).to_le_bytes());
+
+        for (file, (hash, result)) in new.iter()
+        {
+            write_string(&mut bytes, &file.to_string_lossy());
+            bytes.extend(hash.to_le_bytes());
+            bytes.extend(result.nloc.to_le_bytes());
+            bytes.extend(result.cc.to_bits().to_le_bytes());
+            bytes.extend(result.functions.to_le_bytes());
+        }
+
+        fs::write(path, bytes)
+    }
+}
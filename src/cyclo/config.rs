@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use snafu::prelude::*;
+
+use crate::file_parser::{self, Keywords};
+
+
+/// Name of the config file read from the current directory if no other is
+/// given
+pub const CONFIG_FILE: &str = "cyclo.toml";
+
+/// Errors reading a config file
+#[derive(Debug, Snafu)]
+pub enum ConfigError
+{
+    #[snafu(display("Unable to read the config '{}': {source}", path.display()))]
+    ReadConfig { path: PathBuf, source: std::io::Error },
+
+    #[snafu(display("The config '{}' is not valid: {source}", path.display()))]
+    ParseConfig { path: PathBuf, source: toml::de::Error },
+}
+
+/// Keywords for an extension given in a [languages.<extension>] section.
+/// Each list given replaces the built-in one, and the rest are kept
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageConfig
+{
+    pub statements: Option<Vec<String>>,
+    pub logical_ops: Option<Vec<String>>,
+    pub function_def: Option<Vec<String>>,
+    pub comments: Option<Vec<String>>,
+    pub case_insensitive: Option<bool>,
+}
+
+/// The contents of a cyclo.toml
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config
+{
+    #[serde(default)]
+    pub languages: HashMap<String, LanguageConfig>,
+}
+
+/// Keywords live for the whole run and the built-in tables are static, so
+/// the configured ones are leaked rather than threading lifetimes through
+/// the parser
+fn leak(keywords: Vec<String>) -> Vec<&'static str>
+{
+    keywords.into_iter()
+            .map(|k| &*Box::leak(k.into_boxed_str()))
+            .collect()
+}
+
+/// Lowercase every keyword of a case insensitive language. The comments are
+/// stripped before the lines are lowercased, so they're left alone
+fn lowercase(keywords: Keywords) -> Keywords
+{
+    let lower = |keywords: Vec<&str>| leak(keywords.iter().map(|k| k.to_lowercase()).collect());
+
+    Keywords
+    {
+        statements: lower(keywords.statements),
+        logical_ops: lower(keywords.logical_ops),
+        function_defs: lower(keywords.function_defs),
+        line_statements: lower(keywords.line_statements),
+        non_statements: lower(keywords.non_statements),
+        returns: lower(keywords.returns),
+        labels: lower(keywords.labels),
+        preprocessor: lower(keywords.preprocessor),
+        ..keywords
+    }
+}
+
+impl Config
+{
+    /// Read a config file
+    pub fn load(path: &Path) -> Result<Config, ConfigError>
+    {
+        let content = fs::read_to_string(path).context(ReadConfigSnafu { path })?;
+
+        toml::from_str(&content).context(ParseConfigSnafu { path })
+    }
+
+    /// Get the keywords of every configured extension, merged over the
+    /// built-in keywords of the extension's language if it has one
+    pub fn keywords(self) -> BTreeMap<String, Keywords>
+    {
+        self.languages.into_iter()
+                      .map(|(extension, language)|
+                      {
+                          let extension = extension.trim_start_matches('.').to_string();
+                          let filename = format!("file.{}", extension);
+                          let mut keywords = file_parser::get_keywords(file_parser::get_language(&filename)).unwrap_or_default();
+
+                          if let Some(statements) = language.statements
+                          {
+                              keywords.statements = leak(statements);
+                          }
+
+                          if let Some(logical_ops) = language.logical_ops
+                          {
+                              keywords.logical_ops = leak(logical_ops);
+                          }
+
+                          /* the given tokens are searched for instead of
+                           * tracking braces or layout */
+                          if let Some(function_defs) = language.function_def
+                          {
+                              keywords.function_defs = leak(function_defs);
+                              keywords.brace_functions = false;
+                              keywords.layout_functions = false;
+                          }
+
+                          if let Some(comments) = language.comments
+                          {
+                              keywords.comments = leak(comments);
+                          }
+
+                          if let Some(case_insensitive) = language.case_insensitive
+                          {
+                              keywords.case_insensitive = case_insensitive;
+                          }
+
+                          /* the lines are lowercased before matching, so the
+                           * keywords have to be too */
+                          if keywords.case_insensitive
+                          {
+                              keywords = lowercase(keywords);
+                          }
+
+                          (extension, keywords)
+                      })
+                      .collect()
+    }
+}
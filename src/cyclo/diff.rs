@@ -0,0 +1,150 @@
+use crate::file_parser::{self, ParserOptions};
+
+
+/// The complexity of the lines added and removed by a diff for a single file
+#[derive(Debug, Clone)]
+pub struct DiffResult
+{
+    /// The path to the file as it appears in the diff
+    pub file: String,
+    /// Complexity of the added lines
+    pub added: f64,
+    /// Complexity of the removed lines
+    pub removed: f64,
+}
+
+impl DiffResult
+{
+    /// The change in complexity introduced by the diff
+    pub fn delta(&self) -> f64
+    {
+        self.added - self.removed
+    }
+}
+
+/// The lines of a single file's hunks
+#[derive(Default)]
+struct FileHunks
+{
+    file: String,
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl FileHunks
+{
+    /// Count the complexity of the added and removed lines, if the language of
+    /// the file is supported
+    fn count(self, options: &ParserOptions) -> Option<DiffResult>
+    {
+        let keywords = file_parser::keywords_for(&self.file, options)?;
+
+        let (added, _) = file_parser::count_complexity(self.added.into_iter(), &keywords, options);
+        let (removed, _) = file_parser::count_complexity(self.removed.into_iter(), &keywords, options);
+
+        Some(DiffResult { file: self.file, added, removed })
+    }
+}
+
+/// Get the path from a ---/+++ file header, without the a/ or b/ prefix and
+/// any trailing timestamp
+fn header_path(header: &str) -> &str
+{
+    let path = header.split('\t').next().unwrap().trim();
+
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+}
+
+/// Get the number of old and new lines from a hunk header like
+/// @@ -1,5 +1,7 @@. A missing count means a single line
+fn hunk_lengths(header: &str) -> (usize, usize)
+{
+    let mut ranges = header.split_whitespace().skip(1);
+
+    let mut length = |prefix: char| -> usize
+    {
+        ranges.next()
+              .and_then(|r| r.strip_prefix(prefix))
+              .map(|r| r.split(',').nth(1).map(|n| n.parse().unwrap_or(0)).unwrap_or(1))
+              .unwrap_or(0)
+    };
+
+    let old = length('-');
+    let new = length('+');
+
+    (old, new)
+}
+
+/// Parse a unified diff and estimate the complexity of the lines added and
+/// removed in each file, using the same keyword counting as whole files.
+/// Files in languages which aren't supported are left out
+pub fn analyze_diff(diff: &str, options: &ParserOptions) -> Vec<DiffResult>
+{
+    let mut results = Vec::new();
+    let mut current: Option<FileHunks> = None;
+    let mut old_path = "";
+
+    /* lines left in the current hunk, so removed lines starting with -- or
+     * added lines starting with ++ aren't mistaken for file headers */
+    let mut old_remaining: usize = 0;
+    let mut new_remaining: usize = 0;
+
+    for line in diff.lines()
+    {
+        if old_remaining > 0 || new_remaining > 0
+        {
+            let hunks = current.as_mut().unwrap();
+
+            match line.chars().next()
+            {
+                Some('+') => {
+                    hunks.added.push(line[1..].to_string());
+                    new_remaining = new_remaining.saturating_sub(1);
+                },
+                Some('-') => {
+                    hunks.removed.push(line[1..].to_string());
+                    old_remaining = old_remaining.saturating_sub(1);
+                },
+                /* no newline at end of file marker */
+                Some('\\') => {},
+                _ => {
+                    old_remaining = old_remaining.saturating_sub(1);
+                    new_remaining = new_remaining.saturating_sub(1);
+                },
+            }
+        }
+        else if let Some(header) = line.strip_prefix("--- ")
+        {
+            old_path = header_path(header);
+        }
+        else if let Some(header) = line.strip_prefix("+++ ")
+        {
+            /* a deleted file only has its old path */
+            let path = match header_path(header)
+            {
+                "/dev/null" => old_path,
+                path => path,
+            };
+
+            if let Some(hunks) = current.take()
+            {
+                results.extend(hunks.count(options));
+            }
+
+            current = Some(FileHunks { file: path.to_string(), ..Default::default() });
+        }
+        else if line.starts_with("@@") && current.is_some()
+        {
+            (old_remaining, new_remaining) = hunk_lengths(line);
+        }
+    }
+
+    if let Some(hunks) = current.take()
+    {
+        results.extend(hunks.count(options));
+    }
+
+    results
+}
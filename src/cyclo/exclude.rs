@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::Path;
+use globset::{GlobBuilder, GlobMatcher};
+
+
+/// Name of the file at the root of the analyzed directory listing patterns
+/// for paths to skip, in the same format as --exclude
+pub const IGNORE_FILE: &str = ".cycloignore";
+
+/// Patterns for common test files, skipped with --exclude-tests unless other
+/// patterns are given
+pub const DEFAULT_TEST_PATTERNS: [&str; 10] = [
+    "tests/", "test/", "test_*.py", "*_test.py", "*_test.go", "*.test.js", "*.spec.js", "*Test.java",
+    "*_test.c", "*_test.cpp",
+];
+
+/// A single exclude pattern
+#[derive(Debug, Clone)]
+struct Pattern
+{
+    matcher: GlobMatcher,
+    /// Whether the pattern was prefixed with ! to include matches again
+    negated: bool,
+    /// Whether the pattern ended with / so only matches directories
+    dir_only: bool,
+    /// The literal part of the pattern before any wildcards, used to tell if
+    /// a negated pattern could match something inside an excluded directory
+    prefix: String,
+}
+
+/// Gitignore style patterns for paths to skip, relative to the directory
+/// being analyzed. Patterns are checked in order and the last one matching a
+/// path wins, so a pattern prefixed with ! includes paths an earlier pattern
+/// excluded. A pattern without a / matches at any depth
+#[derive(Debug, Default, Clone)]
+pub struct ExcludePatterns
+{
+    patterns: Vec<Pattern>,
+}
+
+impl ExcludePatterns
+{
+    pub fn new(patterns: &[String]) -> Result<ExcludePatterns, globset::Error>
+    {
+        let mut compiled = Vec::new();
+
+        for pattern in patterns
+        {
+            let (negated, pattern) = match pattern.strip_prefix('!')
+            {
+                Some(pattern) => (true, pattern),
+                None => (false, pattern.as_str()),
+            };
+
+            let (dir_only, pattern) = match pattern.strip_suffix('/')
+            {
+                Some(pattern) => (true, pattern),
+                None => (false, pattern),
+            };
+
+            /* a leading / anchors the pattern to the root, otherwise a pattern
+             * without a / can match at any depth */
+            let pattern = match pattern.strip_prefix('/')
+            {
+                Some(pattern) => pattern.to_string(),
+                None if !pattern.contains('/') => format!("**/{}", pattern),
+                None => pattern.to_string(),
+            };
+
+            let matcher = GlobBuilder::new(&pattern).literal_separator(true)
+                                                    .build()?
+                                                    .compile_matcher();
+
+            let prefix = pattern.split(['*', '?', '[', '{']).next().unwrap().to_string();
+
+            compiled.push(Pattern { matcher, negated, dir_only, prefix });
+        }
+
+        Ok(ExcludePatterns { patterns: compiled })
+    }
+
+    /// Read the patterns in the ignore file at the root of a directory, one
+    /// per line. Blank lines and lines starting with # are skipped. There are
+    /// no patterns if the file doesn't exist, and an invalid pattern is
+    /// reported and the whole file is ignored
+    pub fn from_ignore_file(root: &Path) -> ExcludePatterns
+    {
+        match fs::read_to_string(root.join(IGNORE_FILE))
+        {
+            Ok(content) => ExcludePatterns::from_ignore_content(&content),
+            Err(_) => ExcludePatterns::default(),
+        }
+    }
+
+    /// Get the patterns in the contents of an ignore file, like one read out
+    /// of an archive
+    pub fn from_ignore_content(content: &str) -> ExcludePatterns
+    {
+        let patterns: Vec<String> = content.lines()
+                                           .map(|line| line.trim())
+                                           .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                                           .map(|line| line.to_string())
+                                           .collect();
+
+        match ExcludePatterns::new(&patterns)
+        {
+            Ok(ignore) => ignore,
+            Err(e) => {
+                eprintln!("Error: invalid pattern in {}: {}", IGNORE_FILE, e);
+                ExcludePatterns::default()
+            }
+        }
+    }
+
+    /// Get whether the last pattern matching a path excludes it, or None if
+    /// no pattern matches
+    fn last_match(&self, path: &Path, is_dir: bool) -> Option<bool>
+    {
+        self.patterns.iter()
+                     .rev()
+                     .find(|p| (is_dir || !p.dir_only) && p.matcher.is_match(path))
+                     .map(|p| !p.negated)
+    }
+
+    /// Check if a path relative to the root is excluded. A path no pattern
+    /// matches is excluded if the closest directory above it that a pattern
+    /// matches is
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool
+    {
+        let mut is_dir = is_dir;
+
+        for ancestor in path.ancestors().filter(|a| !a.as_os_str().is_empty())
+        {
+            if let Some(excluded) = self.last_match(ancestor, is_dir)
+            {
+                return excluded;
+            }
+
+            is_dir = true;
+        }
+
+        false
+    }
+
+    /// Check if a directory relative to the root can be skipped entirely. An
+    /// excluded directory is still walked if a negated pattern could include
+    /// something inside it
+    pub fn is_pruned(&self, dir: &Path) -> bool
+    {
+        if !self.is_excluded(dir, true)
+        {
+            return false;
+        }
+
+        let dir = format!("{}/", dir.to_string_lossy());
+
+        !self.patterns.iter().any(|p| p.negated && (p.prefix.is_empty() || p.prefix.starts_with(&dir)))
+    }
+}
@@ -1,12 +1,25 @@
-use std::io::{BufReader, BufRead};
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::option::Option;
 use std::result::Result;
-use std::fs::File;
+use std::fs;
+use std::io::Read;
 use std::vec::Vec;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use walkdir::DirEntry;
-use tokei::{Config, Languages, LanguageType};
+use tokei::{Config, LanguageType};
 use snafu::prelude::*;
 
+use crate::algorithm::{Algorithm, ComplexityAlgorithm};
+use crate::braces::BraceTracker;
+use crate::cache::{self, Cache, CachedResult};
+use crate::exclude::ExcludePatterns;
+use crate::layout::LayoutTracker;
+use crate::timings::{Phase, Timings};
+
 
 /// This error is returned if a file is unabled to be parsed due to an
 /// unknown extension. It should never get to this point as there is
@@ -16,6 +29,205 @@ pub enum FileParserError
 {
     #[snafu(display("The file '{file}' has a bad extension and could not be parsed"))]
     BadFileExtension { file: String },
+    #[snafu(display("The file '{file}' could not be read"))]
+    ReadFile { file: String },
+    #[snafu(display("The file '{file}' is not under '{}'", prefix.display()))]
+    NotUnderPrefix { file: String, prefix: PathBuf },
+    #[snafu(display("The path '{file}' could not be walked: {reason}"))]
+    Walk { file: String, reason: String },
+}
+
+impl FileParserError
+{
+    /// Get the name of the kind of error, for grouping the failures
+    pub fn kind(&self) -> &'static str
+    {
+        match self
+        {
+            FileParserError::BadFileExtension { .. } => "BadFileExtension",
+            FileParserError::ReadFile { .. } => "ReadFile",
+            FileParserError::NotUnderPrefix { .. } => "NotUnderPrefix",
+            FileParserError::Walk { .. } => "Walk",
+        }
+    }
+
+    /// Get the file which failed to parse
+    pub fn file(&self) -> &str
+    {
+        match self
+        {
+            FileParserError::BadFileExtension { file }
+            | FileParserError::ReadFile { file }
+            | FileParserError::NotUnderPrefix { file, .. }
+            | FileParserError::Walk { file, .. } => file,
+        }
+    }
+}
+
+/// Branch instructions counted in inline assembly blocks, for x86 and ARM
+const ASM_BRANCHES: [&str; 28] = ["jmp", "je", "jne", "jz", "jnz", "jg", "jge", "jl", "jle", "ja", "jae", "jb", "jbe",
+                                  "jc", "jnc", "jo", "jno", "js", "jns", "jcxz", "jecxz", "loop",
+                                  "beq", "bne", "bgt", "blt", "cbz", "cbnz"];
+
+/// Marks the line it is on as ignored
+const IGNORE: &str = "cyclo:ignore";
+/// Marks the start of a region of ignored lines
+const IGNORE_START: &str = "cyclo:ignore-start";
+/// Marks the end of a region of ignored lines
+const IGNORE_END: &str = "cyclo:ignore-end";
+
+/// Maximum number of physical lines joined into a single logical line, so an
+/// unbalanced parenthesis can't swallow the rest of the file
+const MAX_CONTINUATION_LINES: usize = 32;
+
+/// The most files analyzed in a run, counting only the files which are
+/// parsed successfully
+#[derive(Debug)]
+pub struct FileLimit
+{
+    /// The most files which are analyzed
+    max: usize,
+    /// Files analyzed so far
+    analyzed: AtomicUsize,
+    /// Whether a file was left out because the limit had been reached
+    truncated: AtomicBool,
+}
+
+impl FileLimit
+{
+    pub fn new(max: usize) -> FileLimit
+    {
+        FileLimit { max, analyzed: AtomicUsize::new(0), truncated: AtomicBool::new(false) }
+    }
+
+    /// Whether the limit has been reached, in which case another file is
+    /// left out and the results are marked truncated
+    pub fn is_full(&self) -> bool
+    {
+        let full = self.analyzed.load(Ordering::SeqCst) >= self.max;
+
+        if full
+        {
+            self.truncated.store(true, Ordering::SeqCst);
+        }
+
+        full
+    }
+
+    /// Count a file which was analyzed, returning false and marking the
+    /// results truncated if the limit had already been reached
+    pub fn claim(&self) -> bool
+    {
+        let claimed = self.analyzed.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < self.max).then_some(n + 1)).is_ok();
+
+        if !claimed
+        {
+            self.truncated.store(true, Ordering::SeqCst);
+        }
+
+        claimed
+    }
+
+    /// Whether any file was left out because of the limit
+    pub fn is_truncated(&self) -> bool
+    {
+        self.truncated.load(Ordering::SeqCst)
+    }
+}
+
+/// Options controlling how files are parsed
+#[derive(Debug, Default, Clone)]
+pub struct ParserOptions
+{
+    /// Join continuation lines into logical lines before counting
+    pub logical_lines: bool,
+    /// Include config and data files, with no complexity
+    pub include_data: bool,
+    /// Include Markdown files, analyzing the code in their fenced blocks
+    pub include_markdown: bool,
+    /// Parse files without an extension in the language of the interpreter
+    /// in their shebang
+    pub detect_shebang: bool,
+    /// Only parse files in these languages, or all languages if empty
+    pub languages: Vec<String>,
+    /// Don't parse files with these extensions, unless their language is
+    /// explicitly included
+    pub exclude_extensions: Vec<String>,
+    /// Count conditional preprocessor directives as decision statements
+    pub count_preprocessor: bool,
+    /// Count each return beyond the first in a function as a branch
+    pub count_returns: bool,
+    /// Count the branch instructions in inline assembly blocks
+    pub count_asm: bool,
+    /// Hidden directories which are walked anyway
+    pub unhide: Vec<String>,
+    /// Paths which aren't walked
+    pub exclude: ExcludePatterns,
+    /// Where the time spent in each phase is recorded, if it is timed
+    pub timings: Option<Arc<Timings>>,
+    /// Results of unchanged files from the last run, if caching
+    pub cache: Option<Arc<Cache>>,
+    /// Where the files which fail to parse are collected, to be reported
+    /// together at the end. They are printed as they fail otherwise
+    pub errors: Option<Arc<Mutex<Vec<FileParserError>>>>,
+    /// Keywords configured for extensions in cyclo.toml, used instead of the
+    /// built-in ones
+    pub custom_keywords: BTreeMap<String, Keywords>,
+    /// Canonical path the labels are made relative to, instead of the parent
+    /// of the walked directory
+    pub relative_to: Option<PathBuf>,
+    /// Stop after this many files, so a mistaken path doesn't analyze a
+    /// whole home directory. The limit is shared by every walk using these
+    /// options, like the directories of a path list
+    pub max_files: Option<Arc<FileLimit>>,
+    /// How the complexity of each file is estimated
+    pub algorithm: Algorithm,
+}
+
+/// The language specific tokens searched for when estimating complexity
+#[derive(Debug, Default, Clone)]
+pub struct Keywords
+{
+    /// Tokens marking a line as a comment
+    pub comments: Vec<&'static str>,
+    /// Start and end delimiters of block comments
+    pub block_comments: Vec<(&'static str, &'static str)>,
+    /// Decision statements
+    pub statements: Vec<&'static str>,
+    /// Logical operations
+    pub logical_ops: Vec<&'static str>,
+    /// Tokens marking a function definition
+    pub function_defs: Vec<&'static str>,
+    /// Tokens marking a line as a decision statement only when the line
+    /// starts with them, like a Haskell guard
+    pub line_statements: Vec<&'static str>,
+    /// Tokens marking a line as never being a decision statement, like a
+    /// Haskell type signature
+    pub non_statements: Vec<&'static str>,
+    /// Detect function definitions by tracking braces instead of searching
+    /// for the function definition tokens
+    pub brace_functions: bool,
+    /// Detect function definitions from the top level declarations of a
+    /// layout based language instead of searching for the tokens
+    pub layout_functions: bool,
+    /// Conditional preprocessor directives, only counted as decision
+    /// statements when asked for
+    pub preprocessor: Vec<&'static str>,
+    /// Keywords starting an inline assembly block, whose branch instructions
+    /// are counted when asked for
+    pub asm_blocks: Vec<&'static str>,
+    /// Statements leaving a function early, which are counted beyond the
+    /// first in each function when asked for
+    pub returns: Vec<&'static str>,
+    /// Labels counted every time they appear outside of a string, like the
+    /// case labels of a switch, so labels sharing a body are each counted
+    pub labels: Vec<&'static str>,
+    /// Only match keywords which start or end with a letter as whole words.
+    /// Sigils count as part of a word, so a variable like $if isn't a keyword
+    pub word_boundaries: bool,
+    /// Match the keywords whatever their case, like SQL's. The lines are
+    /// lowercased before matching, so the keywords must be lowercase
+    pub case_insensitive: bool,
 }
 
 /// Struct representing a valid file to be parsed
@@ -25,63 +237,1359 @@ pub struct FileParser<'a>
     pub filename: String,
     /// Raw DirEntry type
     entry: &'a DirEntry,
+    /// Options controlling the parsing
+    options: &'a ParserOptions,
     /// Mean function cyclomatic complexity for the file. Used for the Treemap.
     pub cc: Option<f64>,
     /// Number of lines of code for the file. Used for the Treemap.
     pub nloc: Option<u64>,
+    /// Number of functions detected in the file
+    pub functions: Option<u64>,
     /// The parent directory that the file is in. Used for the Treemap.
     pub parent: Option<String>,
     /// The path to the file from the root, including flename. Used for the
     /// Treemap
-    pub label: Option<String>
+    pub label: Option<String>,
+    /// The language the file was detected as, like c or py
+    pub language: Option<String>,
+    /// How the complexity of the file is estimated
+    algorithm: Box<dyn ComplexityAlgorithm>,
+}
+
+/// The results of parsing a single file, without any of the directory nodes
+/// synthesized for the Treemap
+#[derive(Debug, Clone)]
+pub struct FileResult
+{
+    /// The path to the file as it was walked
+    pub path: PathBuf,
+    /// The path to the file from the root, including filename
+    pub label: String,
+    /// The parent directory that the file is in
+    pub parent: String,
+    /// Number of lines of code for the file
+    pub nloc: u64,
+    /// Complexity for the file
+    pub cc: f64,
+    /// Number of functions detected in the file
+    pub functions: u64,
+    /// The language the file was detected as, like c or py
+    pub language: String,
+}
+
+impl FileResult
+{
+    /// Whether the file has at least `min` functions, which it always does
+    /// without a minimum
+    pub fn has_functions(&self, min: Option<u64>) -> bool
+    {
+        min.is_none_or(|min| self.functions >= min)
+    }
+}
+
+/// Split a path into the components joined to make a label
+fn label_components(path: &Path) -> Vec<String>
+{
+    path.components()
+        .map(|c| match c
+        {
+            Component::RootDir => String::new(),
+            c => c.as_os_str().to_string_lossy().to_string(),
+        })
+        .collect()
 }
 
 /// Check if the file extension can be parsed by this program. Return TRUE if
 /// it can, FALSE if it cannot.
-/// Currently supported extensions are for C, C++, Python, and Javascript
+/// Currently supported extensions are for C, C++, Python, Javascript, Elixir,
+/// Zig, Perl, Haskell, Nim, SQL, and the script blocks of Vue and Svelte
+/// components
 pub fn is_file_extension_valid(file: &str) -> bool
 {
-    let extensions = vec![".c", ".cpp", ".cc", ".cxx", ".py", ".js"];
+    let extensions = [".c", ".cpp", ".cc", ".cxx", ".py", ".js", ".mjs", ".cjs", ".ex", ".exs", ".zig", ".pl", ".pm", ".hs", ".nim", ".vue", ".svelte", ".sql"];
+
+    extensions.iter()
+              .any(|n| file.ends_with(*n))
+}
+
+/// Find a block comment delimiter in a line. POD commands like =pod and =cut
+/// are only recognized at the start of a line, so an assignment like
+/// $x =header() isn't taken as one
+fn find_delimiter(line: &str, delimiter: &str) -> Option<usize>
+{
+    if delimiter.starts_with('=')
+    {
+        return line.starts_with(delimiter).then_some(0);
+    }
+
+    line.find(delimiter)
+}
+
+/// Check if the keyword found at an index of a line is a whole word, so a
+/// keyword starting or ending with a letter can't be preceded or followed by
+/// a word character or sigil
+fn is_whole_word(line: &str, index: usize, keyword: &str) -> bool
+{
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '$' | '@' | '%');
+    let check_start = keyword.starts_with(char::is_alphanumeric);
+    let check_end = keyword.ends_with(char::is_alphanumeric);
+
+    let before = line[..index].chars().next_back();
+    let after = line[index + keyword.len()..].chars().next();
+
+    let joined_start = check_start && before.is_some_and(is_word);
+    let joined_end = check_end && after.is_some_and(is_word);
+
+    !joined_start && !joined_end
+}
+
+/// Check if a keyword appears in a line, optionally only as a whole word
+pub(crate) fn contains_keyword(line: &str, keyword: &str, word_boundaries: bool) -> bool
+{
+    if !word_boundaries
+    {
+        return line.contains(keyword);
+    }
+
+    line.match_indices(keyword).any(|(index, _)| is_whole_word(line, index, keyword))
+}
+
+/// Count the times a keyword appears in a line, optionally only as a whole
+/// word
+pub(crate) fn count_keyword(line: &str, keyword: &str, word_boundaries: bool) -> usize
+{
+    line.match_indices(keyword)
+        .filter(|(index, _)| !word_boundaries || is_whole_word(line, *index, keyword))
+        .count()
+}
+
+/// Count the times a logical operator appears in a line. An operator made of
+/// letters, like Python's and, is always matched as a whole word so it isn't
+/// found inside identifiers like sorted
+pub(crate) fn count_logical_op(line: &str, op: &str, word_boundaries: bool) -> usize
+{
+    count_keyword(line, op, word_boundaries || op.chars().all(char::is_alphabetic))
+}
+
+/// Blank out the contents of the string and character literals in a line,
+/// so keywords inside them aren't counted. An escaped quote doesn't end a
+/// literal
+pub(crate) fn strip_strings(line: &str) -> String
+{
+    let mut stripped = String::with_capacity(line.len());
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next()
+    {
+        match quote
+        {
+            Some(_) if c == '\\' => {
+                chars.next();
+            },
+            Some(q) if c == q => {
+                quote = None;
+                stripped.push(c);
+            },
+            Some(_) => {},
+            None => {
+                if c == '"' || c == '\''
+                {
+                    quote = Some(c);
+                }
+
+                stripped.push(c);
+            },
+        }
+    }
+
+    stripped
+}
+
+/// Check if a line is part of a block comment, updating the state of the
+/// block comment being tracked. `open` is the delimiter closing the current
+/// block comment, or None if not in one. Any line which opens, closes, or is
+/// inside a block comment counts as part of it
+pub(crate) fn is_block_comment<'a>(line: &str, open: &mut Option<&'a str>, block_comments: &[(&str, &'a str)]) -> bool
+{
+    if let Some(end) = open
+    {
+        if find_delimiter(line, end).is_some()
+        {
+            *open = None;
+        }
+
+        return true;
+    }
+
+    for (start, end) in block_comments
+    {
+        if let Some(index) = find_delimiter(line, start)
+        {
+            /* a block comment opened and closed on the same line, like a one
+             * line docstring, doesn't change the state */
+            if !line[index + start.len()..].contains(end)
+            {
+                *open = Some(end);
+            }
+
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Get the language of a file given its name, or an empty string if the
+/// language isn't supported
+pub fn get_language(filename: &str) -> &'static str
+{
+    /* fragile to multiple extensions but that is such an unlikely edge case */
+    match filename.rsplit('.').next().unwrap()
+    {
+        "c" => "c",
+        "cc" => "cpp",
+        "cxx" => "cpp",
+        "cpp" => "cpp",
+        "py" => "py",
+        "js" => "js",
+        /* ES and CommonJS modules */
+        "mjs" => "js",
+        "cjs" => "js",
+        "ex" => "elixir",
+        "exs" => "elixir",
+        "zig" => "zig",
+        "pl" => "perl",
+        "pm" => "perl",
+        "hs" => "haskell",
+        "nim" => "nim",
+        "sql" => "sql",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        "md" => "markdown",
+        "markdown" => "markdown",
+        "toml" => "toml",
+        "yaml" => "yaml",
+        "yml" => "yaml",
+        "json" => "json",
+        _ => ""
+    }
+}
+
+/// Get the name of the language of a file. A language only known from the
+/// config is named by its extension
+pub fn language_name(filename: &str) -> &str
+{
+    match get_language(filename)
+    {
+        "" => filename.rsplit('.').next().unwrap(),
+        language => language,
+    }
+}
+
+/// Get the extension of the language run by the interpreter in a shebang
+/// line, like py for `#!/usr/bin/env python3`, or None if it isn't a shebang
+/// or the interpreter isn't known
+pub fn shebang_extension(line: &str) -> Option<&'static str>
+{
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next().unwrap();
+
+    /* env runs the interpreter after it, skipping any of its own options
+     * like -S */
+    if interpreter == "env"
+    {
+        interpreter = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    }
+
+    /* versioned interpreters like python3.11 run the same language */
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    match interpreter
+    {
+        "python" | "pypy" => Some("py"),
+        "node" | "nodejs" => Some("js"),
+        "perl" => Some("pl"),
+        "elixir" => Some("exs"),
+        "runghc" | "runhaskell" | "stack" => Some("hs"),
+        _ => None,
+    }
+}
+
+/// Number of bytes read from the start of a file without an extension to
+/// look for a shebang
+const SHEBANG_PREFIX: u64 = 256;
+
+/// Get the name a file is parsed as. With --detect-shebang a file without an
+/// extension is parsed as if it had the extension of the interpreter in its
+/// shebang, like tool.py for a tool script starting `#!/usr/bin/env python3`.
+/// Any other file is parsed as its own name
+pub fn parsed_name(path: &Path, options: &ParserOptions) -> String
+{
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+    if !options.detect_shebang || path.extension().is_some()
+    {
+        return name;
+    }
+
+    /* only the start of the file is read, since most of the files won't be
+     * scripts and a binary may have no newline at all */
+    let mut start = Vec::new();
+    if fs::File::open(path).and_then(|f| f.take(SHEBANG_PREFIX).read_to_end(&mut start)).is_err()
+    {
+        return name;
+    }
+
+    let start = String::from_utf8_lossy(&start);
+    let first_line = start.lines().next();
+
+    match first_line.and_then(shebang_extension)
+    {
+        Some(extension) => format!("{}.{}", name, extension),
+        None => name,
+    }
+}
+
+/// Get the keywords searched for when estimating the complexity of a
+/// language, or None if the language isn't supported
+pub fn get_keywords(language: &str) -> Option<Keywords>
+{
+    let keywords = match language
+    {
+        "c" | "cpp" => Keywords
+        {
+            comments: vec!["//", "/*", "*/", "*", "///"],
+            block_comments: vec![("/*", "*/")],
+            /* catch is C++ only, and finally doesn't count since it always
+             * runs. a switch is counted by its case labels */
+            statements: vec!["if(", "if (", "for(", "for (", "while(", "while (", "goto", "catch(", "catch ("],
+            logical_ops: vec!["&&", "||"],
+            function_defs: vec![],
+            line_statements: vec![],
+            non_statements: vec![],
+            brace_functions: true,
+            layout_functions: false,
+            /* #if also covers #ifdef and #ifndef */
+            returns: vec!["return", "throw"],
+            labels: vec!["case"],
+            preprocessor: vec!["#if", "#elif"],
+            asm_blocks: vec!["asm", "__asm__", "__asm"],
+            word_boundaries: false,
+            case_insensitive: false,
+        },
+        "py" => Keywords
+        {
+            /* TODO */
+            comments: vec!["#"],
+            /* docstrings aren't comments but are treated like them */
+            block_comments: vec![("\"\"\"", "\"\"\""), ("'''", "'''")],
+            /* except also covers except*, and finally doesn't count since it
+             * always runs */
+            statements: vec!["if", "for", "while", "break", "except"],
+            logical_ops: vec!["and", "or", "not"],
+            function_defs: vec!["def "],
+            line_statements: vec![],
+            non_statements: vec![],
+            brace_functions: false,
+            layout_functions: false,
+            returns: vec!["return", "raise"],
+            labels: vec![],
+            preprocessor: vec![],
+            asm_blocks: vec![],
+            word_boundaries: false,
+            case_insensitive: false,
+        },
+        /* only the script blocks of single file components are counted */
+        "js" | "vue" | "svelte" => Keywords
+        {
+            /* TODO */
+            comments: vec!["//", "*/", "/*"],
+            block_comments: vec![("/*", "*/")],
+            statements: vec!["if", "for", "while", "catch"],
+            logical_ops: vec!["&&", "||"],
+            function_defs: vec!["function"],
+            line_statements: vec![],
+            non_statements: vec![],
+            brace_functions: false,
+            layout_functions: false,
+            returns: vec!["return", "throw"],
+            labels: vec!["case"],
+            preprocessor: vec![],
+            asm_blocks: vec![],
+            word_boundaries: false,
+            case_insensitive: false,
+        },
+        "elixir" => Keywords
+        {
+            comments: vec!["#"],
+            /* heredocs are almost always @doc and @moduledoc */
+            block_comments: vec![("\"\"\"", "\"\"\"")],
+            /* each -> is a case/cond/fn arm */
+            statements: vec!["if ", "unless ", "cond ", "case ", "->"],
+            /* || won't match a pipe |> so it isn't counted */
+            logical_ops: vec!["&&", "||", " and ", " or "],
+            function_defs: vec!["def ", "defp "],
+            line_statements: vec![],
+            non_statements: vec![],
+            brace_functions: false,
+            layout_functions: false,
+            returns: vec!["raise"],
+            labels: vec![],
+            preprocessor: vec![],
+            asm_blocks: vec![],
+            word_boundaries: false,
+            case_insensitive: false,
+        },
+        "zig" => Keywords
+        {
+            comments: vec!["//"],
+            block_comments: vec![],
+            /* orelse and catch handle errors/nulls so are branches too, and
+             * each => is a switch prong */
+            statements: vec!["if(", "if (", "else", "while(", "while (", "for(", "for (", "switch", "catch", "orelse", "=>"],
+            logical_ops: vec![" and ", " or "],
+            function_defs: vec!["fn "],
+            line_statements: vec![],
+            non_statements: vec![],
+            brace_functions: false,
+            layout_functions: false,
+            returns: vec!["return"],
+            labels: vec![],
+            preprocessor: vec![],
+            asm_blocks: vec![],
+            word_boundaries: false,
+            case_insensitive: false,
+        },
+        "perl" => Keywords
+        {
+            comments: vec!["#"],
+            /* POD documentation runs from any command paragraph to =cut */
+            block_comments: vec![("=pod", "=cut"), ("=head", "=cut"), ("=over", "=cut"), ("=item", "=cut"),
+                                 ("=begin", "=cut"), ("=for", "=cut"), ("=encoding", "=cut")],
+            statements: vec!["if", "elsif", "unless", "for", "foreach", "while", "until"],
+            logical_ops: vec!["&&", "||", "and", "or"],
+            function_defs: vec!["sub "],
+            line_statements: vec![],
+            non_statements: vec![],
+            brace_functions: false,
+            layout_functions: false,
+            returns: vec!["return", "die"],
+            labels: vec![],
+            preprocessor: vec![],
+            asm_blocks: vec![],
+            word_boundaries: true,
+            case_insensitive: false,
+        },
+        "haskell" => Keywords
+        {
+            comments: vec!["--"],
+            block_comments: vec![("{-", "-}")],
+            /* each -> is a case alternative, where and let don't branch */
+            statements: vec!["if", "->"],
+            logical_ops: vec!["&&", "||"],
+            function_defs: vec![],
+            /* each guard is a branch, apart from the otherwise at the end.
+             * the constructors of a data declaration aren't guards */
+            line_statements: vec!["|"],
+            /* arrows in type signatures aren't case alternatives, and the
+             * arrows of lambdas are blanked */
+            non_statements: vec!["::", "otherwise"],
+            brace_functions: false,
+            layout_functions: true,
+            returns: vec![],
+            labels: vec![],
+            preprocessor: vec![],
+            asm_blocks: vec![],
+            word_boundaries: true,
+            case_insensitive: false,
+        },
+        "nim" => Keywords
+        {
+            comments: vec!["#"],
+            block_comments: vec![("#[", "]#")],
+            /* each of is a case branch. the word operators need boundaries
+             * so an identifier like format or notify isn't counted */
+            statements: vec!["if", "elif", "case", "of", "for", "while"],
+            logical_ops: vec!["and", "or", "not"],
+            function_defs: vec!["proc ", "func ", "method "],
+            line_statements: vec![],
+            /* inheriting from an object isn't a case branch */
+            non_statements: vec!["object of"],
+            brace_functions: false,
+            layout_functions: false,
+            returns: vec!["return", "raise"],
+            labels: vec![],
+            preprocessor: vec![],
+            asm_blocks: vec![],
+            word_boundaries: true,
+            case_insensitive: false,
+        },
+        "sql" => Keywords
+        {
+            comments: vec!["--", "/*", "*/"],
+            block_comments: vec![("/*", "*/")],
+            /* each WHEN of a CASE is a branch, and the END IF closing an IF
+             * isn't another one */
+            statements: vec!["case", "if", "elsif", "elseif", "while"],
+            logical_ops: vec!["and", "or"],
+            function_defs: vec!["create procedure", "create function", "create trigger",
+                                "create or replace procedure", "create or replace function", "create or replace trigger"],
+            line_statements: vec![],
+            non_statements: vec!["end if", "end case", "end while"],
+            brace_functions: false,
+            layout_functions: false,
+            returns: vec!["return"],
+            labels: vec!["when"],
+            preprocessor: vec![],
+            asm_blocks: vec![],
+            word_boundaries: true,
+            case_insensitive: true,
+        },
+        _ => return None,
+    };
+
+    Some(keywords)
+}
+
+/// An inline assembly block being read
+struct AsmBlock
+{
+    /// Net number of parentheses and braces opened since the asm keyword
+    depth: i64,
+    /// Whether the block has been opened yet, which may be on the line after
+    /// the keyword
+    opened: bool,
+}
+
+/// Count the branch instructions in a line of an inline assembly block,
+/// like asm volatile ("jne 1f") or __asm { jmp done }. A block starts at an
+/// asm keyword and runs until its parentheses or braces are closed
+fn count_asm_branches(line: &str, keywords: &Keywords, block: &mut Option<AsmBlock>) -> u64
+{
+    let started = block.is_none();
+    let text = match block
+    {
+        Some(_) => line,
+        None => {
+            let start = keywords.asm_blocks.iter()
+                                           .filter_map(|n| line.match_indices(n).find(|(i, _)| is_whole_word(line, *i, n)))
+                                           .map(|(i, _)| i)
+                                           .min();
+
+            match start
+            {
+                Some(start) => {
+                    *block = Some(AsmBlock { depth: 0, opened: false });
+                    &line[start..]
+                },
+                None => return 0,
+            }
+        },
+    };
+
+    let count = ASM_BRANCHES.iter()
+                            .map(|n| count_keyword(text, n, true) as u64)
+                            .sum();
+
+    let asm = block.as_mut().unwrap();
+
+    for c in text.chars()
+    {
+        match c
+        {
+            '(' | '{' => {
+                asm.depth += 1;
+                asm.opened = true;
+            },
+            ')' | '}' => asm.depth -= 1,
+            _ => {},
+        }
+    }
+
+    /* a block which isn't opened by the line after the keyword is a single
+     * statement like __asm nop */
+    if (asm.opened && asm.depth <= 0) || (!asm.opened && !started)
+    {
+        *block = None;
+    }
+
+    count
+}
+
+/// Find where the comment on a line starts, skipping the delimiters inside
+/// string and character literals. An escaped quote doesn't end a literal
+fn comment_start(line: &str, comments: &[&str]) -> Option<usize>
+{
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+
+    for (index, c) in line.char_indices()
+    {
+        match quote
+        {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {},
+            None if comments.iter().any(|n| line[index..].starts_with(*n)) => return Some(index),
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None => {},
+        }
+    }
+
+    None
+}
+
+/// Check if a line is ignored given its comment, tracking whether the next
+/// line is in an ignored region. A region runs from a cyclo:ignore-start
+/// marker to a cyclo:ignore-end marker, and a line with a cyclo:ignore
+/// marker is ignored on its own, like generated code or a big lookup switch
+fn is_ignored(comment: &str, ignoring: &mut bool) -> bool
+{
+    if comment.contains(IGNORE_START)
+    {
+        *ignoring = true;
+        return true;
+    }
+
+    if comment.contains(IGNORE_END)
+    {
+        *ignoring = false;
+        return true;
+    }
+
+    *ignoring || comment.contains(IGNORE)
+}
+
+/// Drop the lines which are marked to be ignored, or in an ignored region.
+/// The markers are only looked for in the comments, so one in a string is
+/// left alone
+fn unignored_lines<'a, I>(lines: I, keywords: &'a Keywords) -> impl Iterator<Item = String> + 'a
+where
+    I: Iterator<Item = String> + 'a,
+{
+    let mut ignoring = false;
+    let mut open_block: Option<&str> = None;
+
+    lines.filter(move |x|
+    {
+        /* the whole of a line inside of a block comment is comment */
+        let comment = match open_block
+        {
+            Some(_) => Some(x.as_str()),
+            None => comment_start(x, &keywords.comments).map(|i| &x[i..]),
+        };
+        is_block_comment(x, &mut open_block, &keywords.block_comments);
+
+        !is_ignored(comment.unwrap_or_default(), &mut ignoring)
+    })
+}
+
+/// Drop the lines in block comments (and python docstrings) and the lines
+/// with a comment
+fn strip_comments<'a, I>(lines: I, keywords: &'a Keywords) -> impl Iterator<Item = String> + 'a
+where
+    I: Iterator<Item = String> + 'a,
+{
+    let mut open_block: Option<&str> = None;
+
+    lines.filter(move |x| !is_block_comment(x, &mut open_block, &keywords.block_comments))
+         .filter(move |x| keywords.comments.iter().all(|n| !x.contains(*n)))
+}
+
+/// Drop the lines the keyword matcher never sees, which are the ignored
+/// lines, the lines in block comments (and python docstrings) and the lines
+/// with a comment
+pub fn matched_lines<'a, I>(lines: I, keywords: &'a Keywords) -> impl Iterator<Item = String> + 'a
+where
+    I: Iterator<Item = String> + 'a,
+{
+    strip_comments(unignored_lines(lines, keywords), keywords)
+}
+
+/// Blank the arrow of each lambda in a line of a layout based language like
+/// Haskell, which is the first -> after its backslash, so only the arrows of
+/// the case alternatives are left
+pub(crate) fn blank_lambda_arrows(line: &str) -> String
+{
+    let mut line = line.to_string();
+    let mut from = 0;
+
+    while let Some(start) = line[from..].find('\\').map(|i| from + i)
+    {
+        let arrow = match line[start..].find("->")
+        {
+            Some(i) => start + i,
+            None => break,
+        };
+
+        line.replace_range(arrow..arrow + 2, "  ");
+        from = arrow + 2;
+    }
+
+    line
+}
+
+/// Estimate the complexity of the lines of a file, returning the complexity
+/// and the number of functions
+pub fn count_complexity<I>(lines: I, keywords: &Keywords, options: &ParserOptions) -> (f64, u64)
+where
+    I: Iterator<Item = String>,
+{
+    let mut logical_ops_count: u64 = 0;
+    let mut function_count: u64 = 0;
+    let mut braces = BraceTracker::new();
+    let mut layout = LayoutTracker::new();
+    /* the number of functions started so far, which identifies the function
+     * each return is in */
+    let function_index = Cell::new(0);
+    /* whether the line is in a Haskell data declaration, where | isn't a
+     * guard */
+    let in_data = Cell::new(false);
+    let mut return_function: u64 = 0;
+    let mut function_returns: u64 = 0;
+    let mut extra_returns_count: u64 = 0;
+    let mut labels_count: u64 = 0;
+    let mut asm_count: u64 = 0;
+    let mut asm_block: Option<AsmBlock> = None;
+
+    /* this is how the iterator works:
+     * - nukes any lines marked to be ignored, or in an ignored region, so
+     * they don't add functions either
+     * - for C/C++, feeds every line to the brace tracker to find the function
+     * definitions, or for Haskell to the layout tracker. this has to see the
+     * lines before any others are nuked
+     * - nukes any block comment lines (and python docstrings), tracking whether
+     * the line is inside of one
+     * - nukes any comment lines because it might fuck with the keyword searching
+     * - lowercases the lines of a case insensitive language, like SQL
+     * - check for logical operations, which may occur on a line more than once
+     * - check for a function definition (this is very guess-y) for the languages
+     * without brace or layout tracking.
+     * - count every label, like a case, outside of the strings
+     * - optionally count the returns beyond the first in each function
+     * - optionally count the branch instructions in inline assembly
+     * - blanks the arrows of Haskell lambdas, which aren't case alternatives
+     * - nuke lines that can't be decision statements, like type signatures
+     * - search for keywords (language specific) and nuke lines that don't have em
+     * - count the lines that are left. this is the number of keywords
+     * - add to this the number of logical operations, labels, extra returns
+     * and assembly branches counted
+     * - done */
+
+    let lines = unignored_lines(lines, keywords);
+    let lines = lines.inspect(|x| if keywords.brace_functions { braces.feed(x); function_index.set(braces.functions) })
+                     .inspect(|x| if keywords.layout_functions { layout.feed(x); function_index.set(layout.functions); in_data.set(layout.in_data()) });
+    let lines = strip_comments(lines, keywords);
+
+    /* optionally join the continuation lines so a wrapped condition is
+     * counted as the single statement it is */
+    let lines: Box<dyn Iterator<Item = String> + '_> = if options.logical_lines
+    {
+        Box::new(LogicalLines { lines })
+    }
+    else
+    {
+        Box::new(lines)
+    };
+
+    /* the keywords of a case insensitive language are lowercase */
+    let lines = lines.map(|x| match keywords.case_insensitive
+    {
+        true => x.to_lowercase(),
+        false => x,
+    });
+
+    /* count the lines as they stream past rather than collecting them, so
+     * memory doesn't grow with the size of the file */
+    let statement_count = lines.inspect(|x| {
+                                   /* estimating number of logical operations, each of
+                                    * which is counted every time it is on the line */
+                                   /* the function definitions are blanked first, since
+                                    * the OR of SQL's CREATE OR REPLACE isn't one */
+                                   let ops_line = keywords.function_defs.iter()
+                                                                        .fold(x.to_string(), |line, n| line.replace(n, &" ".repeat(n.len())));
+
+                                   for item in &keywords.logical_ops
+                                   {
+                                       logical_ops_count += count_logical_op(&ops_line, item, keywords.word_boundaries) as u64;
+                                   }
+
+                                   /* estimating number of functions */
+                                   function_count += if keywords.function_defs.iter().any(|n| contains_keyword(x, n, keywords.word_boundaries)) { 1 } else { 0 };
+
+                                   if !keywords.brace_functions && !keywords.layout_functions
+                                   {
+                                       function_index.set(function_count);
+                                   }
+
+                                   /* every case label of a switch is a branch, even
+                                    * when several share a line */
+                                   if !keywords.labels.is_empty()
+                                   {
+                                       let code = strip_strings(x);
+                                       labels_count += keywords.labels.iter().map(|n| count_keyword(&code, n, true) as u64).sum::<u64>();
+                                   }
+
+                                   /* a return is always a whole word, like return_value isn't */
+                                   if options.count_returns && keywords.returns.iter().any(|n| contains_keyword(x, n, true))
+                                   {
+                                       if function_index.get() != return_function
+                                       {
+                                           return_function = function_index.get();
+                                           function_returns = 0;
+                                       }
+
+                                       function_returns += 1;
+                                       extra_returns_count += if function_returns > 1 { 1 } else { 0 };
+                                   }
+
+                                   if options.count_asm && !keywords.asm_blocks.is_empty()
+                                   {
+                                       asm_count += count_asm_branches(x, keywords, &mut asm_block);
+                                   }
+                                   })
+                               .map(|s| match keywords.layout_functions
+                               {
+                                   true => blank_lambda_arrows(&s),
+                                   false => s,
+                               })
+                               .filter(|s| keywords.non_statements.iter().all(|n| !s.contains(*n)))
+                               .filter(|s| keywords.statements.iter().any(|n| contains_keyword(s, n, keywords.word_boundaries))
+                                        || (!in_data.get() && keywords.line_statements.iter().any(|n| s.trim_start().starts_with(*n)))
+                                        || (options.count_preprocessor
+                                            && keywords.preprocessor.iter().any(|n| s.contains(*n))))
+                               .count();
+
+    let mut complexity_count: u64 = statement_count.try_into().unwrap();
+    complexity_count += logical_ops_count;
+    complexity_count += extra_returns_count;
+    complexity_count += labels_count;
+    complexity_count += asm_count;
+
+    if keywords.brace_functions
+    {
+        function_count = braces.functions;
+    }
+
+    if keywords.layout_functions
+    {
+        function_count = layout.functions;
+    }
+
+    (complexity_count as f64, function_count)
+}
+
+/// Get the net number of parentheses opened on a line
+fn paren_depth(line: &str) -> i64
+{
+    line.chars()
+        .map(|c| match c
+        {
+            '(' => 1,
+            ')' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Iterator adapter joining physical lines into logical lines. A line is
+/// continued onto the next if it ends with a backslash, or if it leaves a
+/// parenthesis open (e.g. a long condition wrapped by clang-format)
+struct LogicalLines<I>
+{
+    lines: I,
+}
+
+impl<I: Iterator<Item = String>> Iterator for LogicalLines<I>
+{
+    type Item = String;
+
+    fn next(&mut self) -> Option<String>
+    {
+        let mut logical = self.lines.next()?;
+        let mut depth = paren_depth(&logical);
+        let mut joined = 1;
+
+        loop
+        {
+            let trimmed = logical.trim_end();
+            let backslash = trimmed.ends_with('\\');
+
+            /* an opened block means the condition is finished even if the
+             * parentheses don't balance */
+            if (!backslash && (depth <= 0 || trimmed.ends_with('{')))
+                || joined >= MAX_CONTINUATION_LINES
+            {
+                return Some(logical);
+            }
+
+            if backslash
+            {
+                logical.truncate(trimmed.len() - 1);
+            }
+
+            match self.lines.next()
+            {
+                Some(line) => {
+                    depth += paren_depth(&line);
+                    joined += 1;
+                    logical.push(' ');
+                    logical.push_str(line.trim());
+                },
+                None => return Some(logical),
+            }
+        }
+    }
+}
+
+/// Get the keywords for a file, preferring the ones configured for its
+/// extension over the built-in ones, or None if the language isn't supported
+pub fn keywords_for(filename: &str, options: &ParserOptions) -> Option<Keywords>
+{
+    let extension = filename.rsplit('.').next().unwrap();
+
+    match options.custom_keywords.get(extension)
+    {
+        Some(keywords) => Some(keywords.clone()),
+        None => get_keywords(get_language(filename)),
+    }
+}
+
+/// Count the lines with code outside of the comments, for the languages
+/// tokei can't count. A line with code before or after a comment counts, a
+/// line which is only comments doesn't. Strings aren't tracked, so a comment
+/// marker in one starts a comment
+pub fn code_lines(content: &str, keywords: &Keywords) -> u64
+{
+    /* the block delimiters are also in the comments, so a line with one is
+     * dropped before matching, but here they're only block comments. So is
+     * the * starting each line inside a block, which is code outside one,
+     * like *p = 0 */
+    let line_comments: Vec<&str> = keywords.comments.iter()
+                                                    .filter(|n| keywords.block_comments.iter().all(|(start, end)| !start.contains(**n) && !end.contains(**n)))
+                                                    .copied()
+                                                    .collect();
+    let mut open: Option<&str> = None;
+    let mut nloc = 0;
+
+    for line in content.lines()
+    {
+        let mut rest = line;
+        let mut code = false;
+
+        loop
+        {
+            if let Some(end) = open
+            {
+                match find_delimiter(rest, end)
+                {
+                    Some(index) => {
+                        rest = &rest[index + end.len()..];
+                        open = None;
+                    },
+                    None => break,
+                }
+            }
+
+            /* whichever comment starts first decides the rest of the line */
+            let block = keywords.block_comments.iter()
+                                               .filter_map(|(start, end)| find_delimiter(rest, start).map(|index| (index, start.len(), Some(*end))))
+                                               .min_by_key(|(index, _, _)| *index);
+            let comment = line_comments.iter()
+                                       .filter_map(|n| rest.find(n).map(|index| (index, n.len(), None)))
+                                       .min_by_key(|(index, _, _)| *index);
+
+            match block.into_iter().chain(comment).min_by_key(|(index, _, _)| *index)
+            {
+                Some((index, len, end)) => {
+                    code |= !rest[..index].trim().is_empty();
+
+                    match end
+                    {
+                        Some(end) => {
+                            rest = &rest[index + len..];
+                            open = Some(end);
+                        },
+                        None => break,
+                    }
+                },
+                None => {
+                    code |= !rest.trim().is_empty();
+                    break;
+                },
+            }
+        }
+
+        nloc += u64::from(code);
+    }
+
+    nloc
+}
+
+/// Get the tokei language type used to count the lines of code of a language
+fn language_type(language: &str) -> Option<LanguageType>
+{
+    let language_type = match language
+    {
+        "c" => LanguageType::C,
+        "cpp" => LanguageType::Cpp,
+        "py" => LanguageType::Python,
+        "js" => LanguageType::JavaScript,
+        "elixir" => LanguageType::Elixir,
+        "zig" => LanguageType::Zig,
+        "perl" => LanguageType::Perl,
+        "haskell" => LanguageType::Haskell,
+        "nim" => LanguageType::Nim,
+        "sql" => LanguageType::Sql,
+        "vue" => LanguageType::Vue,
+        "svelte" => LanguageType::Svelte,
+        "toml" => LanguageType::Toml,
+        "yaml" => LanguageType::Yaml,
+        "json" => LanguageType::Json,
+        _ => return None,
+    };
+
+    Some(language_type)
+}
+
+/// Check if a file is a Vue or Svelte single file component, where only the
+/// script blocks are code
+pub fn is_component(file: &str) -> bool
+{
+    file.ends_with(".vue") || file.ends_with(".svelte")
+}
+
+/// Get the lines of a single file component with everything outside of the
+/// <script> blocks blanked, so expressions in the template and style aren't
+/// counted. The lines are kept so the line numbers still match the file
+pub fn script_lines(content: &str) -> impl Iterator<Item = String> + '_
+{
+    let mut in_script = false;
+
+    content.lines()
+           .map(move |line|
+           {
+               let trimmed = line.trim();
+
+               if in_script && trimmed.starts_with("</script")
+               {
+                   in_script = false;
+                   return String::new();
+               }
+
+               if !in_script && trimmed.starts_with("<script")
+               {
+                   /* a script opened and closed on one line has no lines in it */
+                   in_script = !trimmed.contains("</script");
+                   return String::new();
+               }
+
+               match in_script
+               {
+                   true => line.to_string(),
+                   false => String::new(),
+               }
+           })
+}
+
+/// Check if a file is Markdown, where only the fenced code blocks are code
+pub fn is_markdown(file: &str) -> bool
+{
+    file.ends_with(".md") || file.ends_with(".markdown")
+}
+
+/// Get the extension of the language named by the info string of a fenced
+/// code block, like py for ```python. Anything else is taken as the
+/// extension itself, so ```c or a language from the config still works
+fn fence_extension(info: &str) -> String
+{
+    let tag = info.split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+                  .next()
+                  .unwrap_or("")
+                  .to_lowercase();
+
+    let extension = match tag.as_str()
+    {
+        "python" | "python3" => "py",
+        "javascript" => "js",
+        "c++" => "cpp",
+        "elixir" => "ex",
+        "perl" => "pl",
+        "haskell" => "hs",
+        _ => return tag,
+    };
+
+    extension.to_string()
+}
+
+/// Get the fenced code blocks of a Markdown file which are in a language that
+/// can be analyzed, as the name of a file in that language and the lines of
+/// the block. Prose, indented code, and blocks without a language are skipped
+pub fn fenced_blocks(content: &str, options: &ParserOptions) -> Vec<(String, String)>
+{
+    let mut blocks = Vec::new();
+    /* the fence of the open block and the block so far */
+    let mut open: Option<(String, String, Vec<&str>)> = None;
+
+    for line in content.lines()
+    {
+        let trimmed = line.trim();
+
+        match open.as_mut()
+        {
+            Some((fence, _, lines)) => {
+                /* a closing fence is at least as long as the opening one */
+                let closes = trimmed.starts_with(fence.as_str())
+                             && trimmed.chars().all(|c| fence.starts_with(c));
+
+                if !closes
+                {
+                    lines.push(line);
+                    continue;
+                }
+
+                let (_, filename, lines) = open.take().unwrap();
+
+                if keywords_for(&filename, options).is_some()
+                {
+                    blocks.push((filename, lines.join("\n")));
+                }
+            },
+            None => {
+                let marker = match trimmed.chars().next()
+                {
+                    Some(c) if c == '`' || c == '~' => c,
+                    _ => continue,
+                };
+                let fence: String = trimmed.chars().take_while(|c| *c == marker).collect();
+
+                if fence.len() < 3
+                {
+                    continue;
+                }
+
+                let filename = format!("block.{}", fence_extension(trimmed[fence.len()..].trim()));
+                open = Some((fence, filename, Vec::new()));
+            },
+        }
+    }
+
+    /* a block left open runs to the end of the file */
+    if let Some((_, filename, lines)) = open
+    {
+        if keywords_for(&filename, options).is_some()
+        {
+            blocks.push((filename, lines.join("\n")));
+        }
+    }
+
+    blocks
+}
+
+/// Get the complexity and number of functions of the contents of a file, or
+/// None if the language isn't supported. The fenced blocks of a Markdown
+/// file are each analyzed in their own language and summed
+pub fn source_complexity(filename: &str, content: &str, options: &ParserOptions) -> Option<(f64, u64)>
+{
+    /* data files have nothing to analyze */
+    if is_data_file(filename)
+    {
+        return Some((0.0, 0));
+    }
+
+    if is_markdown(filename)
+    {
+        let totals = fenced_blocks(content, options).iter()
+                                                    .filter_map(|(block, source)| source_complexity(block, source, options))
+                                                    .fold((0.0, 0), |(cc, functions), (x, y)| (cc + x, functions + y));
+
+        return Some(totals);
+    }
+
+    let keywords = keywords_for(filename, options)?;
+
+    if is_component(filename)
+    {
+        return Some(count_complexity(script_lines(content), &keywords, options));
+    }
+
+    Some(count_complexity(content.lines().map(|x| x.to_string()), &keywords, options))
+}
+
+/// Get the lines of the contents of a file as the keyword matcher sees them,
+/// for debugging an estimate that looks wrong. The comments and ignored
+/// lines are dropped and, with logical lines, the continuation lines are
+/// joined. Strings are left as they are, since their contents are only
+/// blanked when counting labels. Returns None if the language isn't supported
+pub fn stripped_source(filename: &str, content: &str, options: &ParserOptions) -> Option<Vec<String>>
+{
+    if is_markdown(filename)
+    {
+        let lines = fenced_blocks(content, options).iter()
+                                                   .filter_map(|(block, source)| stripped_source(block, source, options))
+                                                   .flatten()
+                                                   .collect();
+
+        return Some(lines);
+    }
+
+    let keywords = keywords_for(filename, options)?;
+    let lines: Box<dyn Iterator<Item = String>> = match is_component(filename)
+    {
+        true => Box::new(script_lines(content)),
+        false => Box::new(content.lines().map(|x| x.to_string())),
+    };
+    let lines = matched_lines(lines, &keywords);
+
+    let stripped = match options.logical_lines
+    {
+        true => LogicalLines { lines }.collect(),
+        false => lines.collect(),
+    };
+
+    Some(stripped)
+}
+
+/// Get the number of lines of code in the contents of a file, or None if the
+/// language isn't supported. Languages tokei can't count, like the ones only
+/// known from the config, have their lines of code counted from their
+/// comments instead
+pub fn source_nloc(filename: &str, content: &str, options: &ParserOptions) -> Option<u64>
+{
+    /* only the code in the fenced blocks of Markdown is counted */
+    if is_markdown(filename)
+    {
+        let nloc = fenced_blocks(content, options).iter()
+                                                  .filter_map(|(block, source)| source_nloc(block, source, options))
+                                                  .sum();
+
+        return Some(nloc);
+    }
+
+    let language_type = match language_type(get_language(filename))
+    {
+        Some(language_type) => language_type,
+        None => return Some(code_lines(content, &keywords_for(filename, options)?)),
+    };
+
+    /* embedded languages, like the script in a component, are children */
+    Some(language_type.parse_from_str(content, &Config::default()).summarise().code as u64)
+}
+
+/// Parse the contents of a file which isn't read from disk, like an archive
+/// entry, returning the complexity, the number of functions, and the number
+/// of lines of code. Returns None if the language isn't supported
+pub fn parse_source(filename: &str, content: &str, options: &ParserOptions) -> Option<(f64, u64, u64)>
+{
+    let (cc, functions) = options.algorithm.implementation().complexity(filename, content, options)?;
+    let nloc = source_nloc(filename, content, options)?;
+
+    Some((cc, functions, nloc))
+}
+
+/// Check if a file is a config or data file (TOML, YAML, or JSON). These have
+/// no complexity, but can be included in the Treemap for their lines of code
+pub fn is_data_file(file: &str) -> bool
+{
+    let extensions = [".toml", ".yaml", ".yml", ".json"];
 
     extensions.iter()
               .any(|n| file.ends_with(*n))
 }
 
-/// Check if a directory is hidden. Return TRUE if hidden, FALSE if not
-pub fn is_hidden(entry: &DirEntry) -> bool
+/// Check if a file can be parsed with the given options. An explicitly
+/// included language wins over an excluded extension
+pub fn is_file_valid(file: &str, options: &ParserOptions) -> bool
 {
+    let extension = file.rsplit('.').next().unwrap();
+    let configured = file.contains('.') && options.custom_keywords.contains_key(extension);
+    let supported = is_file_extension_valid(file) || configured
+                    || (options.include_data && is_data_file(file))
+                    || (options.include_markdown && is_markdown(file));
+
+    if !supported
+    {
+        return false;
+    }
+
+    let language = language_name(file);
+    let included = options.languages.iter().any(|l| l == language);
+
+    if !options.languages.is_empty() && !included
+    {
+        return false;
+    }
+
+    let excluded = options.exclude_extensions.iter()
+                                             .any(|e| e.trim_start_matches('.') == extension);
+
+    !excluded || included
+}
+
+/// Check if a directory is hidden. Return TRUE if hidden, FALSE if not.
+/// Directories named in `unhide` aren't hidden, and neither is the root of
+/// the walk so that paths like `.` can be analyzed
+pub fn is_hidden(entry: &DirEntry, unhide: &[String]) -> bool
+{
+    if entry.depth() == 0
+    {
+        return false;
+    }
+
     entry.file_name()
          .to_str()
-         .map(|s| s.starts_with("."))
+         .map(|s| s.starts_with('.') && !unhide.iter().any(|u| u == s))
          .unwrap_or(false)
 }
 
 
 impl<'a> FileParser<'_>
 {
-    pub fn new (entry: &'a DirEntry) -> FileParser<'a>
+    pub fn new (entry: &'a DirEntry, options: &'a ParserOptions) -> FileParser<'a>
     {
         FileParser
         {
-            filename: entry.file_name().to_os_string().into_string().unwrap(),
-            entry: entry,
+            /* a name which isn't valid UTF-8 is converted lossily rather than
+             * panicking, the file is still read through its path */
+            filename: parsed_name(entry.path(), options),
+            entry,
+            options,
             cc: None,
             nloc: None,
+            functions: None,
             parent: None,
-            label: None
+            label: None,
+            language: None,
+            algorithm: options.algorithm.implementation(),
         }
     }
 
-    /// Walk through a file, retrieving the cumulative complexity and the number
-    /// of lines of code. Also parses the file path to extract the values for the
-    /// Treemap, returning successfully if this is successful and returning
-    /// an error if the file is otherwise unable to be parsed
-    pub fn file_walk(&mut self) -> Result<(), FileParserError>
+    /// Record the time since `start` for a phase, if it is being timed
+    fn record(&self, phase: Phase, start: Instant)
+    {
+        if let Some(timings) = &self.options.timings
+        {
+            timings.record(phase, start);
+        }
+    }
+
+    /// Count the complexity, functions, and lines of code of the contents of
+    /// the file
+    fn count(&mut self, content: &str) -> Result<(), FileParserError>
     {
         /* first get the mean of function complexities for the file */
-        match self.get_file_complexity()
+        let start = Instant::now();
+        let complexity = self.get_file_complexity(content);
+        self.record(Phase::Complexity, start);
+
+        match complexity
         {
-            Some(complexity) => self.cc = Some(complexity),
+            Some((complexity, functions)) => {
+                self.cc = Some(complexity);
+                self.functions = Some(functions);
+            },
             _ => {
                 return BadFileExtensionSnafu
                 {
@@ -91,7 +1599,11 @@ impl<'a> FileParser<'_>
         }
 
         /* then get the nloc for the file */
-        match self.get_file_nloc()
+        let start = Instant::now();
+        let nloc = self.get_file_nloc(content);
+        self.record(Phase::Nloc, start);
+
+        match nloc
         {
             Some(nloc) => self.nloc = Some(nloc),
             _ => {
@@ -102,44 +1614,94 @@ impl<'a> FileParser<'_>
             }
         }
 
-        /* finally set the values as vec elements for the treemap */
-        let depth = self.entry.depth();
+        Ok(())
+    }
+
+    /// Walk through a file, retrieving the cumulative complexity and the number
+    /// of lines of code. Also parses the file path to extract the values for the
+    /// Treemap, returning successfully if this is successful and returning
+    /// an error if the file is otherwise unable to be parsed
+    pub fn file_walk(&mut self) -> Result<(), FileParserError>
+    {
+        /* the file is only read once for both the complexity and the nloc */
+        let content = match fs::read(self.entry.path())
+        {
+            Ok(content) => content,
+            Err(_) => {
+                return ReadFileSnafu
+                {
+                    file: &self.filename,
+                }.fail()
+            }
+        };
+
+        /* files which haven't changed since the cache was written aren't
+         * counted again */
+        let cache = self.options.cache.as_ref().map(|cache| (cache, cache::content_hash(&content)));
+
+        match cache.and_then(|(cache, hash)| cache.get(self.entry.path(), hash))
+        {
+            Some(cached) => {
+                self.cc = Some(cached.cc);
+                self.functions = Some(cached.functions);
+                self.nloc = Some(cached.nloc);
+            },
+            None => {
+                /* any invalid UTF-8 is replaced rather than failing the whole file */
+                self.count(&String::from_utf8_lossy(&content))?;
+
+                if let Some((cache, hash)) = cache
+                {
+                    let result = CachedResult { nloc: self.nloc.unwrap(), cc: self.cc.unwrap(), functions: self.functions.unwrap() };
+                    cache.insert(self.entry.path(), hash, result);
+                }
+            },
+        }
+
+        self.language = Some(language_name(&self.filename).to_string());
 
-        let len = self.entry.path().to_str().unwrap()
-                                   .split("/").count();
+        /* finally set the values as vec elements for the treemap. The path
+         * is split into components rather than on a separator, so the labels
+         * are joined with forward slashes on every platform */
+        let mut full_path = match &self.options.relative_to
+        {
+            /* the labels are rooted at the prefix instead of the walk */
+            Some(prefix) => {
+                let path = self.entry.path().canonicalize().unwrap_or_else(|_| self.entry.path().to_path_buf());
 
-        let mut full_path = self.entry.path().to_str().unwrap()
-                                  .split("/")
-                                  .collect::<Vec<&str>>();
+                match path.strip_prefix(prefix)
+                {
+                    Ok(relative) => label_components(relative),
+                    Err(_) => {
+                        return NotUnderPrefixSnafu
+                        {
+                            file: self.entry.path().to_string_lossy(),
+                            prefix,
+                        }.fail()
+                    }
+                }
+            },
+            None => {
+                let depth = self.entry.depth();
+                let components = label_components(self.entry.path());
+
+                components[components.len()-depth-1..].to_vec()
+            },
+        };
 
         /* the label is /path/to/file.c */
-        self.label = Some(full_path[len-depth-1..].join("/"));
+        self.label = Some(full_path.join("/"));
 
         full_path.pop();
 
         /* the parent is /path/to */
-        self.parent = Some(full_path[len-depth-1..].join("/"));
+        self.parent = Some(full_path.join("/"));
         Ok(())
     }
 
-    /// Get the file extension given a file name
-    fn get_file_extension(&mut self) -> &str
-    {
-        /* fragile to multiple extensions but that is such an unlikely edge case */
-        match self.filename.as_str().rsplit(".").next().unwrap()
-        {
-            "c" => "c",
-            "cc" => "cpp",
-            "cxx" => "cpp",
-            "cpp" => "cpp",
-            "py" => "py",
-            "js" => "js",
-            _ => ""
-        }
-    }
-
-    /// Get the mean function complexity in a file by manually searching for
-    /// decision statements and logical operations
+    /// Get the mean function complexity in a file with the chosen algorithm,
+    /// by default by manually searching for decision statements and logical
+    /// operations, along with the number of functions in the file
     /// NOTE: Accuracy is questionable but the estimated complexity _should_
     /// be close to the actual. HOWEVER its magitudes better than the
     /// previous method of generating ASTs since there is a dearth of libraries
@@ -147,128 +1709,14 @@ impl<'a> FileParser<'_>
     /// tree-sitter is awesome but was very fragile when dealing with
     /// C/C++ preprocessor directives. doing it the below way is simpler and
     /// returns a reasonable approximation of the actual cyclomatic complexity.
-    fn get_file_complexity(&mut self) -> Option<f64>
+    fn get_file_complexity(&mut self, content: &str) -> Option<(f64, u64)>
     {
-        let mut comments: Vec<&str> = Vec::new();
-        let mut statements: Vec<&str> = Vec::new();
-        let mut logical_ops: Vec<&str> = Vec::new();
-        let function_def: &str;
-
-        /* identify the extension */
-        match self.get_file_extension()
-        {
-            "c" => {
-                comments.extend(["//", "/*", "*/", "*", "///"].iter());
-                statements.extend(["if(", "if (", "for(", "for (", "while(", "while (", "switch", "break", "goto"].iter());
-                logical_ops.extend(["&&", "||"].iter());
-                function_def = "return";
-
-            },
-            "cpp" => {
-                comments.extend(["//", "/*", "*/", "*", "///"].iter());
-                statements.extend(["if(", "if (", "for(", "for (", "while(", "while (", "switch", "break", "goto"].iter());
-                logical_ops.extend(["&&", "||"].iter());
-                function_def = "return";
-            },
-            "py" => {
-                /* TODO */
-                comments.extend(["#"].iter());
-                statements.extend(["if", "for", "while", "break"].iter());
-                logical_ops.extend(["and", "or", "not"].iter());
-                function_def = "def ";
-            },
-            "js" => {
-                /* TODO */
-                comments.extend(["//", "*/", "/*"].iter());
-                statements.extend(["if", "for", "while"].iter());
-                logical_ops.extend(["&&", "||"].iter());
-                function_def = "function";
-            },
-            _ => { return None; },
-        }
-
-        let mut logical_ops_count: u64 = 0;
-        let mut function_count: u64 = 0;
-
-        let path = self.entry.path();
-        let f = File::open(&path).unwrap();
-        let reader = BufReader::new(f).lines();
-
-        /* this is how the iterator works:
-         * - nukes any comment lines because it might fuck with the keyword searching
-         * - check for logical operations, which may occur on a line more than once
-         * - check for a function definition (this is very guess-y). for C/C++ it counts
-         * the number of returns. some functions may have more than one, and some functions
-         * may have none. hopefully it evens out.
-         * - search for keywords (language specific) and nuke lines that don't have em
-         * - collect it all into a vec. the size is the number of keywords
-         * - add to this the number of logical operations counted
-         * - done */
-
-        let valid_lines: Vec<String> = reader.map(|x| x.unwrap())
-                                    .filter(|x| comments.iter().all(|n| !x.contains(*n)))
-                                    .inspect(|x| {
-                                        /* estimating number of logical operations */
-                                        for item in &logical_ops
-                                        {
-                                            logical_ops_count += if x.contains(item) { 1 } else { 0 };
-                                        }
-
-                                        /* estimating number of functions */
-                                        function_count += if x.contains(function_def) { 1 } else { 0 };
-                                        })
-                                    .filter(|s| statements.iter().any(|n| s.contains(*n)))
-                                    .collect();
-
-        let mut complexity_count: u64 = valid_lines.len().try_into().unwrap();
-        complexity_count += logical_ops_count;
-
-        let mean_complexity: f64;
-
-        if function_count == 0
-        {
-            mean_complexity = 0.0;
-        }
-        else
-        {
-            mean_complexity = complexity_count as f64 / function_count as f64;
-        }
-
-//        return Some(mean_complexity);
-        return Some(complexity_count as f64);
+        self.algorithm.complexity(&self.filename, content, self.options)
     }
 
     /// Get the number of lines of code in a file
-    fn get_file_nloc(&mut self) -> Option<u64>
+    fn get_file_nloc(&mut self, content: &str) -> Option<u64>
     {
-        let path = &[self.entry.path().to_str().unwrap()];
-        let excluded = &[];
-
-        let config = Config::default();
-        let mut languages = Languages::new();
-
-        languages.get_statistics(path, excluded, &config);
-
-        /* manually identify the extension */
-        match self.get_file_extension()
-        {
-            "c" => {
-                let lang = &languages[&LanguageType::C];
-                Some(lang.code.try_into().unwrap())
-            },
-            "cpp" => {
-                let lang = &languages[&LanguageType::Cpp];
-                Some(lang.code.try_into().unwrap())
-            },
-            "py" => {
-                let lang = &languages[&LanguageType::Python];
-                Some(lang.code.try_into().unwrap())
-            },
-            "js" => {
-                let lang = &languages[&LanguageType::JavaScript];
-                Some(lang.code.try_into().unwrap())
-            },
-            _ => None,
-        }
+        source_nloc(&self.filename, content, self.options)
     }
 }
@@ -0,0 +1,138 @@
+use std::fs;
+use serde::{Deserialize, Serialize};
+
+use crate::braces::BraceTracker;
+use crate::file_parser::{self, FileResult, Keywords, ParserOptions};
+use crate::layout::LayoutTracker;
+
+
+/// The source of a function, for showing the most complex functions to
+/// reviewers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snippet
+{
+    /// Label of the file the function is in
+    pub label: String,
+    /// First line of the function, numbered from 1
+    pub start: usize,
+    /// Last line of the function
+    pub end: usize,
+    /// Estimated complexity of the function
+    pub cc: f64,
+    /// The lines of the function
+    pub source: String,
+}
+
+/// Find the first and last lines of each function, numbered from 1. Brace
+/// languages use the blocks found by the brace tracker, otherwise a function
+/// runs from its definition to the last non-blank line before the next one.
+/// Layout languages find the definitions with the layout tracker
+pub fn function_spans(lines: &[String], keywords: &Keywords) -> Vec<(usize, usize)>
+{
+    if keywords.brace_functions
+    {
+        let mut braces = BraceTracker::new();
+
+        for line in lines
+        {
+            braces.feed(line);
+        }
+
+        let mut spans = braces.spans;
+        spans.sort_unstable();
+
+        return spans;
+    }
+
+    let starts: Vec<usize> = if keywords.layout_functions
+    {
+        let mut layout = LayoutTracker::new();
+
+        for line in lines
+        {
+            layout.feed(line);
+        }
+
+        layout.starts
+    }
+    else
+    {
+        let mut open_block: Option<&str> = None;
+
+        lines.iter()
+             .enumerate()
+             .filter(|(_, x)| !file_parser::is_block_comment(x, &mut open_block, &keywords.block_comments))
+             .filter(|(_, x)| keywords.comments.iter().all(|n| !x.contains(*n)))
+             .map(|(i, x)| (i, match keywords.case_insensitive { true => x.to_lowercase(), false => x.to_string() }))
+             .filter(|(_, x)| keywords.function_defs.iter().any(|n| file_parser::contains_keyword(x, n, keywords.word_boundaries)))
+             .map(|(i, _)| i + 1)
+             .collect()
+    };
+
+    starts.iter()
+          .enumerate()
+          .map(|(i, &start)|
+          {
+              let mut end = starts.get(i + 1).map(|next| next - 1).unwrap_or(lines.len());
+
+              while end > start && lines[end - 1].trim().is_empty()
+              {
+                  end -= 1;
+              }
+
+              (start, end)
+          })
+          .collect()
+}
+
+/// Get the functions of a file with their complexities
+pub fn file_snippets(file: &FileResult, options: &ParserOptions) -> Vec<Snippet>
+{
+    let name = file_parser::parsed_name(&file.path, options);
+    let keywords = match file_parser::keywords_for(&name, options)
+    {
+        Some(keywords) => keywords,
+        None => return Vec::new(),
+    };
+
+    /* read lossily like the walk, so a line which isn't UTF-8 doesn't cut
+     * the functions short */
+    let lines: Vec<String> = match fs::read(&file.path)
+    {
+        Ok(content) => String::from_utf8_lossy(&content).lines().map(String::from).collect(),
+        Err(_) => return Vec::new(),
+    };
+
+    /* only the script blocks of a component have functions */
+    let lines: Vec<String> = match file_parser::is_component(&name)
+    {
+        true => file_parser::script_lines(&lines.join("\n")).collect(),
+        false => lines,
+    };
+
+    function_spans(&lines, &keywords).into_iter()
+                                     .map(|(start, end)|
+                                     {
+                                         let body = &lines[start - 1..end];
+                                         let (cc, _) = file_parser::count_complexity(body.iter().cloned(), &keywords, options);
+
+                                         Snippet { label: file.label.clone(), start, end, cc, source: body.join("\n") }
+                                     })
+                                     .collect()
+}
+
+/// Get the source of the `n` most complex functions over all the files, most
+/// complex first
+pub fn worst_functions(files: &[FileResult], n: usize, options: &ParserOptions) -> Vec<Snippet>
+{
+    let mut snippets: Vec<Snippet> = files.iter()
+                                          .flat_map(|f| file_snippets(f, options))
+                                          .collect();
+
+    snippets.sort_by(|a, b| b.cc.total_cmp(&a.cc)
+                             .then_with(|| a.label.cmp(&b.label))
+                             .then_with(|| a.start.cmp(&b.start)));
+    snippets.truncate(n);
+
+    snippets
+}
@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::fs;
+
+use crate::file_parser::{self, FileResult, Keywords, ParserOptions};
+
+
+/// Operators made of symbols, shared by every language. The longest are
+/// first so that e.g. <<= isn't split into << and =. Closing brackets aren't
+/// counted, a pair is counted once by its opening bracket
+const SYMBOLS: [&str; 54] = [
+    "<<=", ">>=", "**=", "//=", "===", "!==", "...",
+    "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+    "<<", ">>", "**", "//", "::", "|>", "<>", ":=", "..", "?.", "??", "<-",
+    "+", "-", "*", "/", "%", "=", "<", ">", "!", "~", "&", "|", "^", "?", ":", ".", ",",
+];
+
+/// Opening brackets and terminators, which are single character operators
+const PUNCTUATION: [char; 4] = ['(', '[', '{', ';'];
+
+/// Counts of the operators and operands of a file, which the Halstead
+/// metrics are computed from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Halstead
+{
+    /// Number of different operators
+    pub distinct_operators: u64,
+    /// Number of different operands
+    pub distinct_operands: u64,
+    /// Total number of operators
+    pub operators: u64,
+    /// Total number of operands
+    pub operands: u64,
+}
+
+impl Halstead
+{
+    /// Number of different operators and operands
+    pub fn vocabulary(&self) -> u64
+    {
+        self.distinct_operators + self.distinct_operands
+    }
+
+    /// Total number of operators and operands
+    pub fn length(&self) -> u64
+    {
+        self.operators + self.operands
+    }
+
+    /// The length times the bits needed to pick each token from the
+    /// vocabulary, or zero if there is nothing to pick between
+    pub fn volume(&self) -> f64
+    {
+        match self.vocabulary()
+        {
+            0 | 1 => 0.0,
+            vocabulary => self.length() as f64 * (vocabulary as f64).log2(),
+        }
+    }
+
+    /// How hard the code is to write or understand, which grows with the
+    /// number of operators and how often the operands are reused. Zero if
+    /// there are no operands
+    pub fn difficulty(&self) -> f64
+    {
+        match self.distinct_operands
+        {
+            0 => 0.0,
+            distinct => (self.distinct_operators as f64 / 2.0) * (self.operands as f64 / distinct as f64),
+        }
+    }
+}
+
+/// A token of source code
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token
+{
+    Operator(String),
+    Operand(String),
+}
+
+/// Get the keywords of a language which are operators: its decision
+/// statements, logical operations, function definitions and returns. Tokens
+/// like `if (` are reduced to the word, symbols like -> are left to SYMBOLS
+fn keyword_operators(keywords: &Keywords) -> HashSet<String>
+{
+    keywords.statements.iter()
+                       .chain(&keywords.logical_ops)
+                       .chain(&keywords.function_defs)
+                       .chain(&keywords.returns)
+                       .map(|k| k.trim().trim_end_matches('(').trim())
+                       .filter(|k| !k.is_empty() && k.chars().all(|c| c.is_alphanumeric() || c == '_'))
+                       .map(|k| k.to_string())
+                       .collect()
+}
+
+/// Split a line into operators and operands. Words are operands unless they
+/// are keyword operators, and numbers and string literals are operands.
+/// Anything else which isn't a known operator, like a sigil, is skipped
+fn tokens(line: &str, operators: &HashSet<String>) -> Vec<Token>
+{
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len()
+    {
+        let c = chars[i];
+
+        if c.is_alphanumeric() || c == '_'
+        {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || (chars[start].is_ascii_digit() && chars[i] == '.'))
+            {
+                i += 1;
+            }
+
+            let word: String = chars[start..i].iter().collect();
+            tokens.push(match operators.contains(&word)
+            {
+                true => Token::Operator(word),
+                false => Token::Operand(word),
+            });
+        }
+        else if c == '"' || c == '\''
+        {
+            /* the literal runs to the matching unescaped quote, or the end
+             * of the line */
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != c
+            {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(chars.len());
+
+            tokens.push(Token::Operand(chars[start..i].iter().collect()));
+        }
+        else if PUNCTUATION.contains(&c)
+        {
+            tokens.push(Token::Operator(c.to_string()));
+            i += 1;
+        }
+        else
+        {
+            let rest: String = chars[i..].iter().take(3).collect();
+
+            match SYMBOLS.iter().find(|s| rest.starts_with(**s))
+            {
+                Some(symbol) => {
+                    tokens.push(Token::Operator(symbol.to_string()));
+                    i += symbol.chars().count();
+                },
+                None => i += 1,
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Count the operators and operands in lines of source code which have had
+/// their comments stripped
+pub fn measure<I: IntoIterator<Item = String>>(lines: I, keywords: &Keywords) -> Halstead
+{
+    let keyword_operators = keyword_operators(keywords);
+    let mut operators = HashSet::new();
+    let mut operands = HashSet::new();
+    let mut halstead = Halstead::default();
+
+    for line in lines
+    {
+        let line = match keywords.case_insensitive { true => line.to_lowercase(), false => line };
+
+        for token in tokens(&line, &keyword_operators)
+        {
+            match token
+            {
+                Token::Operator(operator) => {
+                    halstead.operators += 1;
+                    operators.insert(operator);
+                },
+                Token::Operand(operand) => {
+                    halstead.operands += 1;
+                    operands.insert(operand);
+                },
+            }
+        }
+    }
+
+    halstead.distinct_operators = operators.len() as u64;
+    halstead.distinct_operands = operands.len() as u64;
+
+    halstead
+}
+
+/// Count the operators and operands in the contents of a file, or None if
+/// the language isn't supported
+pub fn source_halstead(filename: &str, content: &str, options: &ParserOptions) -> Option<Halstead>
+{
+    let keywords = file_parser::keywords_for(filename, options)?;
+    let lines = file_parser::stripped_source(filename, content, options)?;
+
+    Some(measure(lines, &keywords))
+}
+
+/// Count the operators and operands of a parsed file, reading it again. None
+/// if it can't be read or its language isn't supported
+pub fn file_halstead(file: &FileResult, options: &ParserOptions) -> Option<Halstead>
+{
+    let content = fs::read(&file.path).ok()?;
+
+    source_halstead(&file_parser::parsed_name(&file.path, options), &String::from_utf8_lossy(&content), options)
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use snafu::prelude::*;
+
+use crate::file_parser::FileResult;
+
+
+/// Errors getting the churn of files from git
+#[derive(Debug, Snafu)]
+pub enum HotspotError
+{
+    #[snafu(display("Unable to run git: {source}"))]
+    RunGit { source: io::Error },
+
+    #[snafu(display("git failed: {stderr}"))]
+    GitFailed { stderr: String },
+}
+
+/// A file ranked by its complexity and how often it changes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hotspot
+{
+    pub label: String,
+    pub cc: f64,
+    /// Number of commits changing the file in the window
+    pub commits: u64,
+    /// Complexity multiplied by the number of commits
+    pub score: f64,
+}
+
+/// Run git in a directory, returning its stdout
+fn git(dir: &Path, args: &[&str]) -> Result<String, HotspotError>
+{
+    let output = Command::new("git").arg("-C")
+                                    .arg(dir)
+                                    .args(args)
+                                    .output()
+                                    .context(RunGitSnafu)?;
+
+    ensure!(output.status.success(), GitFailedSnafu { stderr: String::from_utf8_lossy(&output.stderr).trim().to_string() });
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Count the commits changing each file in the git repository containing
+/// `dir` since `since`, which is anything git log --since accepts like
+/// "6 months ago". The paths are absolute
+pub fn churn(dir: &Path, since: &str) -> Result<HashMap<PathBuf, u64>, HotspotError>
+{
+    let toplevel = PathBuf::from(git(dir, &["rev-parse", "--show-toplevel"])?.trim());
+    let toplevel = toplevel.canonicalize().unwrap_or(toplevel);
+
+    /* paths with unusual characters are quoted by git unless told not to */
+    let log = git(dir, &["-c", "core.quotePath=false", "log", &format!("--since={}", since), "--format=", "--name-only"])?;
+
+    let mut commits = HashMap::new();
+
+    for file in log.lines().filter(|l| !l.is_empty())
+    {
+        *commits.entry(toplevel.join(file)).or_insert(0) += 1;
+    }
+
+    Ok(commits)
+}
+
+/// Get the files under `dir` which differ from `reference`, which is
+/// anything git diff accepts like main or HEAD~3, along with the untracked
+/// files which aren't ignored. The paths are relative to `dir`, and files
+/// which were deleted are left in for the caller to skip
+pub fn changed(dir: &Path, reference: &str) -> Result<Vec<PathBuf>, HotspotError>
+{
+    let diff = git(dir, &["-c", "core.quotePath=false", "diff", "--name-only", "--relative", reference, "--"])?;
+    let untracked = git(dir, &["-c", "core.quotePath=false", "ls-files", "--others", "--exclude-standard"])?;
+
+    let mut files: Vec<PathBuf> = diff.lines()
+                                      .chain(untracked.lines())
+                                      .filter(|l| !l.is_empty())
+                                      .map(PathBuf::from)
+                                      .collect();
+    files.sort();
+    files.dedup();
+
+    Ok(files)
+}
+
+/// Rank the files by their complexity multiplied by their number of commits,
+/// highest first. Files which didn't change in the window are left out
+pub fn hotspots(files: &[FileResult], churn: &HashMap<PathBuf, u64>) -> Vec<Hotspot>
+{
+    let mut hotspots: Vec<Hotspot> = files.iter()
+                                          .filter_map(|f|
+                                          {
+                                              let path = f.path.canonicalize().ok()?;
+                                              let commits = *churn.get(&path)?;
+
+                                              Some(Hotspot { label: f.label.clone(), cc: f.cc, commits, score: f.cc * commits as f64 })
+                                          })
+                                          .collect();
+
+    hotspots.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.label.cmp(&b.label)));
+
+    hotspots
+}
@@ -0,0 +1,80 @@
+/// Top level declarations which aren't functions
+const DECLARATIONS: [&str; 11] = [
+    "module", "import", "data", "type", "newtype", "class", "instance", "deriving", "infix", "infixl", "infixr",
+];
+
+/// Tracks the top level declarations of a layout based language like Haskell
+/// to detect function definitions. A function is a run of unindented lines
+/// binding the same name, so its type signature and every equation of it are
+/// only counted once
+#[derive(Debug, Default)]
+pub struct LayoutTracker
+{
+    /// The name bound by the last top level line
+    name: Option<String>,
+    /// Whether a block comment is open
+    in_comment: bool,
+    /// Whether the last top level line started a data or newtype declaration
+    in_data: bool,
+    /// Number of lines fed so far
+    line: usize,
+    /// Number of functions detected
+    pub functions: u64,
+    /// Line each function starts on, numbered from 1
+    pub starts: Vec<usize>,
+}
+
+impl LayoutTracker
+{
+    pub fn new() -> LayoutTracker
+    {
+        LayoutTracker::default()
+    }
+
+    /// Whether the lines are in a data or newtype declaration, where a line
+    /// starting with | is another constructor rather than a guard
+    pub fn in_data(&self) -> bool
+    {
+        self.in_data
+    }
+
+    /// Feed the next line of the file to the tracker
+    pub fn feed(&mut self, line: &str)
+    {
+        self.line += 1;
+
+        if self.in_comment
+        {
+            self.in_comment = !line.contains("-}");
+            return;
+        }
+
+        if line.trim_start().starts_with("{-")
+        {
+            self.in_comment = !line.contains("-}");
+            return;
+        }
+
+        /* indented lines and blank lines are inside the last declaration */
+        if line.trim().is_empty() || line.starts_with(char::is_whitespace) || line.starts_with("--")
+        {
+            return;
+        }
+
+        let name = line.split_whitespace().next().unwrap();
+        self.in_data = matches!(name, "data" | "newtype");
+
+        if DECLARATIONS.contains(&name) || !(line.contains('=') || line.contains("::"))
+        {
+            self.name = None;
+            return;
+        }
+
+        if self.name.as_deref() != Some(name)
+        {
+            self.functions += 1;
+            self.starts.push(self.line);
+            self.name = Some(name.to_string());
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+use walkdir::{DirEntry, WalkDir};
+
+pub mod algorithm;
+pub mod annotate;
+pub mod archive;
+pub mod baseline;
+pub mod braces;
+pub mod cache;
+pub mod config;
+pub mod diff;
+pub mod exclude;
+pub mod file_parser;
+pub mod functions;
+pub mod halstead;
+pub mod hotspots;
+pub mod layout;
+pub mod normalize;
+pub mod output;
+pub mod remote;
+pub mod term;
+pub mod timings;
+pub mod treemap;
+pub mod validate;
+pub mod watch;
+
+use exclude::ExcludePatterns;
+use file_parser::{FileParser, FileParserError, FileResult, ParserOptions};
+use timings::Phase;
+
+
+/// Walk a directory, yielding every entry which isn't hidden, excluded, or
+/// ignored by a .cycloignore at the root, whether or not it can be parsed.
+/// The entries which can't be walked, like a directory which can't be read
+/// or a symlink loop, are recorded as failures
+fn walk_unfiltered(path: &Path, options: &ParserOptions) -> impl Iterator<Item = DirEntry>
+{
+    walk_subtree(path, path, usize::MAX, options)
+}
+
+/// Walk a directory under the root of a tree, up to a depth below it,
+/// skipping the same entries a walk of the whole tree would
+fn walk_subtree(root: &Path, dir: &Path, max_depth: usize, options: &ParserOptions) -> impl Iterator<Item = DirEntry>
+{
+    let options = options.clone();
+    let unhide = options.unhide.clone();
+    let exclude = options.exclude.clone();
+    let file_exclude = exclude.clone();
+    let ignore = ExcludePatterns::from_ignore_file(root);
+    let file_ignore = ignore.clone();
+    let root = root.to_path_buf();
+    let file_root = root.clone();
+
+    /* the symlinked directories are followed, and a link back to a directory
+     * above it is an error rather than an endless walk */
+    WalkDir::new(dir).max_depth(max_depth)
+                     .follow_links(true)
+                     .into_iter()
+                     .filter_entry(move |e|
+                     {
+                         /* an excluded or ignored directory isn't walked at all */
+                         let relative = e.path().strip_prefix(&root).unwrap();
+                         let pruned = e.file_type().is_dir() && (exclude.is_pruned(relative) || ignore.is_pruned(relative));
+
+                         !pruned && !file_parser::is_hidden(e, &unhide)
+                     })
+                     .filter_map(move |e| match e
+                     {
+                         Ok(e) => Some(e),
+                         Err(e) => {
+                             let file = e.path().unwrap_or(Path::new("")).to_string_lossy().into_owned();
+                             record_error(&options, FileParserError::Walk { file, reason: e.to_string() });
+                             None
+                         },
+                     })
+                     .filter(move |e|
+                     {
+                         let relative = e.path().strip_prefix(&file_root).unwrap();
+
+                         !file_exclude.is_excluded(relative, false) && !file_ignore.is_excluded(relative, false)
+                     })
+}
+
+/// Walk a directory, yielding the entries of every file which isn't hidden,
+/// excluded, or ignored by a .cycloignore at the root, and is valid with the
+/// given options. A file reached through more than one path (e.g. via a
+/// symlink) is only yielded the first time it is reached, and a broken
+/// symlink is recorded as a failure like a symlink loop. The walk stops once
+/// the maximum number of files has been analyzed, which marks the results
+/// truncated if there was another file
+pub fn walk(path: &Path, options: &ParserOptions) -> impl Iterator<Item = DirEntry>
+{
+    let options = options.clone();
+    let limit = options.max_files.clone();
+    let mut seen = HashSet::new();
+
+    walk_unfiltered(path, &options).filter(move |e| file_parser::is_file_valid(&file_parser::parsed_name(e.path(), &options), &options))
+                                   .filter(move |e| match fs::canonicalize(e.path())
+                                   {
+                                       Ok(canonical) => seen.insert(canonical),
+                                       Err(_) => false,
+                                   })
+                                   .take_while(move |_| !limit.as_ref().is_some_and(|l| l.is_full()))
+}
+
+/// Walk the directories under a directory in a tree and the files in them
+/// which would be analyzed, up to a depth below it, for watching the tree
+/// for changes. The files are filtered like a walk of the whole tree, apart
+/// from the maximum number of files
+pub fn walk_watched(root: &Path, dir: &Path, max_depth: usize, options: &ParserOptions) -> impl Iterator<Item = DirEntry>
+{
+    let options = options.clone();
+
+    walk_subtree(root, dir, max_depth, &options).filter(move |e| e.file_type().is_dir() || file_parser::is_file_valid(&file_parser::parsed_name(e.path(), &options), &options))
+}
+
+/// Count the files under a directory which are skipped because they can't
+/// be parsed with the given options, by extension. Hidden, excluded, and
+/// ignored files aren't counted since they are skipped on purpose. The most
+/// common extensions are first
+pub fn skipped(path: &Path, options: &ParserOptions) -> Vec<(String, u64)>
+{
+    let mut counts: HashMap<String, u64> = HashMap::new();
+
+    for entry in walk_unfiltered(path, options).filter(|e| !e.file_type().is_dir())
+    {
+        let name = file_parser::parsed_name(entry.path(), options);
+
+        if file_parser::is_file_valid(&name, options)
+        {
+            continue;
+        }
+
+        let extension = match entry.path().extension()
+        {
+            Some(extension) => format!(".{}", extension.to_string_lossy()),
+            None => "(none)".to_string(),
+        };
+
+        *counts.entry(extension).or_insert(0) += 1;
+    }
+
+    let mut counts: Vec<(String, u64)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    counts
+}
+
+/// Analyze every valid file under a directory, returning an iterator over the
+/// results as they are parsed. The walk feeds a bounded channel of entries
+/// consumed by the worker threads, so the results are yielded in the order
+/// the workers finish rather than the order of the walk. Dropping the
+/// iterator early stops the walk and the workers
+pub fn analyze_iter(path: &Path, options: &ParserOptions, threads: usize) -> impl Iterator<Item = FileResult>
+{
+    let threads = threads.max(1);
+    let (entry_tx, entry_rx) = mpsc::sync_channel::<DirEntry>(threads * 4);
+    let (result_tx, result_rx) = mpsc::channel::<FileResult>();
+
+    /* the receiver can't be shared between threads on its own */
+    let entry_rx = Arc::new(Mutex::new(entry_rx));
+    let options = Arc::new(options.clone());
+
+    for _ in 0..threads
+    {
+        let entry_rx = Arc::clone(&entry_rx);
+        let result_tx = result_tx.clone();
+        let options = Arc::clone(&options);
+
+        thread::spawn(move ||
+        {
+            loop
+            {
+                /* the lock is only held while receiving, not parsing */
+                let entry = match entry_rx.lock().unwrap().recv()
+                {
+                    Ok(entry) => entry,
+                    Err(_) => break,
+                };
+
+                match parse_entry(&entry, &options)
+                {
+                    /* the walk runs ahead of the workers, so a file parsed
+                     * after the limit was reached is dropped */
+                    Ok(_) if options.max_files.as_ref().is_some_and(|l| !l.claim()) => {},
+                    Ok(result) => {
+                        /* the consumer has gone away */
+                        if result_tx.send(result).is_err()
+                        {
+                            break;
+                        }
+                    },
+                    Err(e) => record_error(&options, e),
+                }
+            }
+        });
+    }
+
+    let mut walker = walk(path, &options);
+
+    thread::spawn(move ||
+    {
+        loop
+        {
+            /* only the walk is timed, not waiting on the workers */
+            let start = Instant::now();
+            let entry = walker.next();
+
+            if let Some(timings) = &options.timings
+            {
+                timings.record(Phase::Walk, start);
+            }
+
+            let entry = match entry
+            {
+                Some(entry) => entry,
+                None => break,
+            };
+
+            /* the channel is closed once the workers have all stopped */
+            if entry_tx.send(entry).is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    result_rx.into_iter()
+}
+
+/// Analyze every valid file under a directory, returning the results sorted
+/// by label so they don't depend on the order the workers finish in
+pub fn analyze(path: &Path, options: &ParserOptions, threads: usize) -> Vec<FileResult>
+{
+    let mut files: Vec<FileResult> = analyze_iter(path, options, threads).collect();
+    files.sort_by(|a, b| a.label.cmp(&b.label));
+
+    files
+}
+
+/// Check if a path is a file listing the directories to analyze, which is
+/// detected by a .txt or .list extension
+pub fn is_path_list(path: &Path) -> bool
+{
+    path.is_file() && path.extension().is_some_and(|e| e == "txt" || e == "list")
+}
+
+/// Read a file listing the directories to analyze, one per line, like the
+/// services of a monorepo. Blank lines and lines starting with # are skipped.
+/// The directories are relative to the directory the list is in
+pub fn read_path_list(list: &Path) -> io::Result<Vec<PathBuf>>
+{
+    let base = list.parent().unwrap_or_else(|| Path::new(""));
+    let paths = fs::read_to_string(list)?.lines()
+                                         .map(|l| l.trim())
+                                         .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                                         .map(|l| base.join(l))
+                                         .collect();
+
+    Ok(paths)
+}
+
+/// Analyze several directories together, like the ones in a path list. Each
+/// directory's files are labelled with the path to it from the base, so
+/// directories with the same name don't collide and ones which share a
+/// parent are merged under it in the treemap. The results are sorted by label
+pub fn analyze_dirs(base: &Path, dirs: &[PathBuf], options: &ParserOptions, threads: usize) -> Vec<FileResult>
+{
+    let mut files = Vec::new();
+
+    for dir in dirs
+    {
+        let mut results = analyze(dir, options, threads);
+
+        /* labels rooted at --relative-to don't depend on the walk */
+        if options.relative_to.is_none()
+        {
+            let prefix: Vec<String> = dir.strip_prefix(base)
+                                         .unwrap_or(dir)
+                                         .components()
+                                         .filter(|c| matches!(c, Component::Normal(_)))
+                                         .map(|c| c.as_os_str().to_string_lossy().to_string())
+                                         .collect();
+
+            /* the walk labels start at the name of the directory, which the
+             * prefix replaces */
+            let root = dir.components().next_back().map(|c| c.as_os_str().to_string_lossy().to_string()).unwrap_or_default();
+
+            if !prefix.is_empty()
+            {
+                let prefix = prefix.join("/");
+
+                for file in &mut results
+                {
+                    file.label = format!("{}{}", prefix, &file.label[root.len()..]);
+                    file.parent = format!("{}{}", prefix, &file.parent[root.len()..]);
+                }
+            }
+        }
+
+        files.extend(results);
+    }
+
+    files.sort_by(|a, b| a.label.cmp(&b.label));
+
+    files
+}
+
+/// Analyze only some of the files under a directory, given relative to it,
+/// like the files changed since a git ref. The files are labelled as if the
+/// directory had been walked, so only the directories above them are in the
+/// treemap. Files which no longer exist, can't be parsed, or would be skipped
+/// by the walk because they are hidden, excluded, or ignored are left out
+pub fn analyze_paths(path: &Path, relative: &[PathBuf], options: &ParserOptions) -> Vec<FileResult>
+{
+    let ignore = ExcludePatterns::from_ignore_file(path);
+    /* the labels of a walk start at the name of the directory walked */
+    let root = path.components().next_back().map(|c| c.as_os_str().to_string_lossy().to_string());
+    let mut files = Vec::new();
+
+    for r in relative
+    {
+        let hidden = r.parent().into_iter()
+                               .flat_map(|p| p.components())
+                               .map(|c| c.as_os_str().to_string_lossy())
+                               .any(|c| c.starts_with('.') && !options.unhide.iter().any(|u| *u == c));
+
+        /* a script without an extension is valid by its shebang, like in a walk */
+        let name = file_parser::parsed_name(&path.join(r), options);
+
+        if hidden || options.exclude.is_excluded(r, false) || ignore.is_excluded(r, false)
+            || !path.join(r).is_file() || !file_parser::is_file_valid(&name, options)
+        {
+            continue;
+        }
+
+        let mut file = match analyze_file(&path.join(r), options)
+        {
+            Ok(file) => file,
+            Err(e) => {
+                record_error(options, e);
+                continue;
+            }
+        };
+
+        if options.relative_to.is_none()
+        {
+            let components: Vec<String> = root.iter()
+                                              .cloned()
+                                              .chain(r.components().map(|c| c.as_os_str().to_string_lossy().to_string()))
+                                              .collect();
+
+            file.label = components.join("/");
+            file.parent = components[..components.len() - 1].join("/");
+        }
+
+        files.push(file);
+    }
+
+    files.sort_by(|a, b| a.label.cmp(&b.label));
+
+    files
+}
+
+/// Analyze a single file. There is no directory hierarchy so the file has no
+/// parent
+pub fn analyze_file(path: &Path, options: &ParserOptions) -> Result<FileResult, FileParserError>
+{
+    let entry = match WalkDir::new(path).into_iter().next()
+    {
+        Some(Ok(entry)) => entry,
+        _ => return Err(FileParserError::ReadFile { file: path.to_string_lossy().to_string() }),
+    };
+
+    if !file_parser::is_file_valid(&file_parser::parsed_name(entry.path(), options), options)
+    {
+        return Err(FileParserError::BadFileExtension { file: path.to_string_lossy().to_string() });
+    }
+
+    parse_entry(&entry, options)
+}
+
+/// Collect a file which failed to parse, or print it straight away if the
+/// failures aren't being collected
+fn record_error(options: &ParserOptions, error: FileParserError)
+{
+    match &options.errors
+    {
+        Some(errors) => errors.lock().unwrap().push(error),
+        None => eprintln!("Error: {}", error),
+    }
+}
+
+/// Parse a single walked entry into its result
+fn parse_entry(entry: &DirEntry, options: &ParserOptions) -> Result<FileResult, FileParserError>
+{
+    let mut file = FileParser::new(entry, options);
+
+    file.file_walk()?;
+
+    Ok(FileResult
+    {
+        path: PathBuf::from(entry.path()),
+        label: file.label.unwrap(),
+        parent: file.parent.unwrap(),
+        nloc: file.nloc.unwrap(),
+        cc: file.cc.unwrap(),
+        functions: file.functions.unwrap(),
+        language: file.language.unwrap(),
+    })
+}
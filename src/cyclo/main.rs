@@ -1,138 +1,1442 @@
-use std::{fs,assert_eq,assert};
-use std::io::Write;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 use std::vec::Vec;
 use clap::Parser;
-use walkdir::WalkDir;
 
-mod file_parser;
+use cyclo::algorithm::Algorithm;
+use cyclo::cache::Cache;
+use cyclo::config::{Config, CONFIG_FILE};
+use cyclo::exclude::{self, ExcludePatterns};
+use cyclo::file_parser::{self, FileLimit, FileResult, Keywords, ParserOptions};
+use cyclo::functions::{self, Snippet};
+use cyclo::halstead::{self, Halstead};
+use cyclo::normalize::Normalizer;
+use cyclo::output::{self, Format, JsonReport};
+use cyclo::remote::RemoteClone;
+use cyclo::term::{self, Color};
+use cyclo::timings::{self, Phase, Timings};
+use cyclo::treemap::{self, DirRollup, GroupBy, MeanMode, SortKey, Treemap};
+use cyclo::validate::{self, ValidateError};
 
-use file_parser::FileParser;
 
+/// How often the tree is checked for changes with --watch
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An error which has already been printed, so the run only has to stop
+#[derive(Debug)]
+struct Failed;
 
 #[derive(Parser,Debug)]
 #[clap(name="cyclo", about="visualize complexity")]
 struct Args
 {
-    /// Relative path to directory to analyze
-    #[clap(short = 'p', long, value_parser)]
+    /// Relative path to directory or file to analyze, a .tar, .tar.gz, or
+    /// .zip archive to analyze without extracting it, the URL of a git
+    /// repository to shallow clone into a temporary directory, or a .txt or
+    /// .list file of directories like --paths-from. Not needed with --diff
+    #[clap(short = 'p', long, value_parser, default_value = ".")]
     path: PathBuf,
     /// Whether to write a debug file
     #[clap(short = 'd', long, action)]
     debug: bool,
+    /// Whether to print a histogram of file complexities to stderr
+    #[clap(long, action)]
+    histogram: bool,
+    /// Print a table of this many of the most complex files under their
+    /// directories to stderr, colored by how they compare to the mean
+    /// complexity when stderr is a terminal and NO_COLOR isn't set
+    #[clap(long, value_parser)]
+    top: Option<usize>,
+    /// Print a one line summary of the files, the mean complexity, and the
+    /// most complex file to stderr once the output is written
+    #[clap(long, action)]
+    summary: bool,
+    /// Print only the headline numbers as JSON to stdout, in place of the
+    /// --format output: the number of files, the lines of code, the mean,
+    /// median and highest complexity, and the same for each language
+    #[clap(long, action, conflicts_with = "output-dir")]
+    summary_json: bool,
+    /// Indent the JSON printed by --format json and --summary-json, and
+    /// written to --output-dir, so it's easier to read and diff by hand,
+    /// rather than keeping it compact
+    #[clap(long, action)]
+    pretty: bool,
+    /// Check the treemap data and JSON written are well formed once they're
+    /// written, exiting with an error if not: every array of the treemap is
+    /// valid with a value for each node, and every parent is a node
+    #[clap(long, action)]
+    validate: bool,
+    /// Print the files which would be analyzed with the filters applied, and
+    /// how many there are, without parsing them
+    #[clap(long, action)]
+    dry_run: bool,
+    /// Analyze --path this many times, like tests/fixtures, and print the
+    /// statistics of the times taken, for checking a change to cyclo's own
+    /// performance. The cache isn't used
+    #[clap(long, value_parser)]
+    bench_self: Option<usize>,
+    /// Keep watching --path once the output is written, parsing only the
+    /// files which change and updating their nodes and the directories above
+    /// them before writing the output again. The known files are checked for
+    /// changes every second, and only the directories which changed are
+    /// walked again to find new files
+    #[clap(long, action, conflicts_with_all = &["since", "functions", "normalize", "treemap-depth", "min-cc", "max-files"])]
+    watch: bool,
+    /// Output format. js writes the treemap data for the webserver, sarif
+    /// prints a SARIF log of the files above the threshold to stdout, junit
+    /// prints a JUnit report failing the files above the threshold to
+    /// stdout, json prints the parsed files to stdout, and dot prints the
+    /// file hierarchy as a Graphviz graph to stdout
+    #[clap(short = 'f', long, value_enum, default_value_t = Format::Js)]
+    format: Format,
+    /// Directory to write the treemap data (cyclo.js), the JSON report
+    /// (cyclo.json), and a standalone dashboard (index.html) to, instead of
+    /// the --format output
+    #[clap(short = 'o', long, value_parser)]
+    output_dir: Option<PathBuf>,
+    /// Complexity above which a file is reported as too complex
+    #[clap(short = 't', long, value_parser, default_value_t = output::DEFAULT_THRESHOLD)]
+    threshold: f64,
+    /// Flag the files whose complexity is more than this factor times the
+    /// mean complexity of the files, exiting with an error if there are any.
+    /// Overrides --threshold for the SARIF log and JUnit report
+    #[clap(long, value_parser)]
+    relative_threshold: Option<f64>,
+    /// Exit with an error if the mean complexity in the middle of the
+    /// treemap colorscale, computed with --mean-mode, is above this
+    #[clap(long, value_parser)]
+    max_mean: Option<f64>,
+    /// Join continuation lines into logical lines before counting
+    #[clap(long, action)]
+    logical_lines: bool,
+    /// Count conditional preprocessor directives (#if, #ifdef, #ifndef,
+    /// #elif) in C/C++ as decision statements
+    #[clap(long, action)]
+    count_preprocessor: bool,
+    /// Count each return, throw, or raise beyond the first in a function as a
+    /// branch
+    #[clap(long, action)]
+    count_returns: bool,
+    /// Count the branch instructions (jmp, je, jne, ...) in inline assembly
+    /// blocks in C/C++ as decision statements
+    #[clap(long, action)]
+    count_asm: bool,
+    /// How the complexity of each file is estimated. The functions drawn
+    /// with --functions are always counted by their decisions
+    #[clap(long, value_enum, default_value_t = Algorithm::DecisionCount, alias = "complexity-algorithm")]
+    algorithm: Algorithm,
+    /// Add the Halstead volume and difficulty of each file, counted from its
+    /// operators and operands, to the JSON output alongside the complexity
+    #[clap(long, action)]
+    halstead: bool,
+    /// Include config and data files (TOML, YAML, JSON) in the treemap, sized
+    /// by their lines of code but with no complexity
+    #[clap(long, action)]
+    include_data: bool,
+    /// Include Markdown files, analyzing the code in their fenced blocks
+    /// (e.g. ```python) by language and ignoring the prose
+    #[clap(long, action)]
+    include_markdown: bool,
+    /// Parse files without an extension, like the scripts under bin/, in the
+    /// language of the interpreter in their shebang (e.g. #!/usr/bin/env
+    /// python3). Files without a known shebang are still skipped
+    #[clap(long, action, alias = "include-extensionless")]
+    detect_shebang: bool,
+    /// Comma separated languages to parse (c, cpp, py, js, elixir, zig, perl,
+    /// haskell, nim, sql, vue, svelte, markdown), defaulting to all of them
+    #[clap(long, value_parser, use_value_delimiter = true)]
+    languages: Vec<String>,
+    /// Comma separated file extensions to skip. A language passed to
+    /// --languages is still parsed even if its extension is excluded
+    #[clap(long, value_parser, use_value_delimiter = true)]
+    exclude_extensions: Vec<String>,
+    /// Name of a hidden directory to analyze anyway, e.g. .ci. Can be
+    /// repeated
+    #[clap(long, value_parser)]
+    unhide: Vec<String>,
+    /// Gitignore style pattern for paths to skip, relative to --path, e.g.
+    /// vendor/**. A pattern starting with ! includes paths an earlier pattern
+    /// excluded. Can be repeated, and the last matching pattern wins
+    #[clap(long, value_parser)]
+    exclude: Vec<String>,
+    /// Skip test files, like test_*.py or anything under a tests directory.
+    /// They are skipped before --exclude is applied, so it can include some
+    /// of them again
+    #[clap(long, action)]
+    exclude_tests: bool,
+    /// Pattern for test files skipped by --exclude-tests, replacing the
+    /// default patterns. Can be repeated
+    #[clap(long, value_parser)]
+    test_pattern: Vec<String>,
+    /// Whether to print the number of files skipped because their language
+    /// isn't supported to stderr, by extension
+    #[clap(long, action)]
+    report_skipped: bool,
+    /// Whether to print the progress of the analysis to stderr. Ignored when
+    /// stderr isn't a terminal
+    #[clap(long, action)]
+    progress: bool,
+    /// Hide files with a complexity below this from the treemap
+    #[clap(long, value_parser)]
+    min_cc: Option<f64>,
+    /// Drop the files with fewer functions than this from the output, like
+    /// files of constants or data
+    #[clap(long, value_parser)]
+    min_functions: Option<u64>,
+    /// Name displayed for the root of the treemap instead of its path. Not
+    /// used when grouping by language, since each language is a root
+    #[clap(long, value_parser)]
+    root_label: Option<String>,
+    /// How the files are grouped at the top of the treemap
+    #[clap(long, value_enum, default_value_t = GroupBy::Dir)]
+    group_by: GroupBy,
+    /// Draw the treemap at most this many levels deep, the root being the
+    /// first. The files below are collapsed into a node for their directory
+    /// at the last level, with their total lines of code and mean complexity,
+    /// so they are still counted
+    #[clap(long, value_parser)]
+    treemap_depth: Option<usize>,
+    /// Include the source of this many of the most complex functions in the
+    /// js and json output
+    #[clap(long, value_parser)]
+    snippets: Option<usize>,
+    /// Add a node under each file in the treemap for each of its functions
+    #[clap(long, action)]
+    functions: bool,
+    /// How the mean complexity in the middle of the treemap colorscale is
+    /// computed
+    #[clap(long, value_enum, default_value_t = MeanMode::Simple)]
+    mean_mode: MeanMode,
+    /// Map the complexities in the treemap onto a scale from 0 to 100,
+    /// relative to the least and most complex files, so runs over different
+    /// codebases can be compared. The JSON keeps the raw complexity alongside
+    #[clap(long, action)]
+    normalize: bool,
+    /// Map this complexity to 100 when normalizing, instead of the most
+    /// complex file, so the scale is the same across runs
+    #[clap(long, value_parser, requires = "normalize")]
+    normalize_max: Option<f64>,
+    /// Color the treemap with a colorblind safe colorscale
+    #[clap(long, action)]
+    accessible: bool,
+    /// Write the treemap data as an ES module exporting jsondata and
+    /// snippets, for importing into another page. The webserver's dashboard
+    /// reads globals, so it can't show it
+    #[clap(long, action)]
+    js_module: bool,
+    /// How the complexity of each directory is computed from the files below
+    /// it, so directories are colored in the treemap
+    #[clap(long, value_enum, default_value_t = DirRollup::None)]
+    dir_rollup: DirRollup,
+    /// Sort the output so it doesn't depend on the order of the walk
+    #[clap(long, value_enum)]
+    sort: Option<SortKey>,
+    /// Unified diff file. Only the lines added and removed by the diff are
+    /// counted, and the change in complexity of each file is printed
+    #[clap(long, value_parser)]
+    diff: Option<PathBuf>,
+    /// Baseline written by --write-baseline to compare against. The files
+    /// whose complexity went up are printed to stderr, and cyclo exits with
+    /// an error if there are any
+    #[clap(long, value_parser)]
+    baseline: Option<PathBuf>,
+    /// Only fail the --baseline comparison if the complexity of a file
+    /// already in the baseline, or the total complexity, rose. New files
+    /// only count towards the total
+    #[clap(long, action, requires = "baseline")]
+    assert_no_increase: bool,
+    /// How much the complexity can rise before --assert-no-increase fails
+    #[clap(long, value_parser, default_value_t = 0.0, requires = "assert-no-increase")]
+    tolerance: f64,
+    /// Write the analysis to this file as a baseline for --baseline
+    #[clap(long, value_parser)]
+    write_baseline: Option<PathBuf>,
+    /// Number of threads used to parse files
+    #[clap(short = 'j', long, value_parser, default_value_t = default_threads())]
+    threads: usize,
+    /// Whether to print the time spent in each phase to stderr
+    #[clap(long, action)]
+    timings: bool,
+    /// Cache the results in this file, so files which haven't changed since
+    /// the last run aren't parsed again
+    #[clap(long, value_parser)]
+    cache: Option<PathBuf>,
+    /// Config file with keywords for extra or overridden languages. Defaults
+    /// to cyclo.toml in the current directory, if there is one
+    #[clap(long, value_parser)]
+    config: Option<PathBuf>,
+    /// Rank the files by their complexity multiplied by their number of
+    /// commits in the git history instead of writing the treemap
+    #[clap(long, action)]
+    hotspots: bool,
+    /// How far back in the git history commits are counted for --hotspots,
+    /// in any format git log --since accepts
+    #[clap(long, value_parser, default_value = "1 year ago")]
+    churn_window: String,
+    /// Only analyze the files which differ from this git ref, like main or
+    /// HEAD, along with the untracked files
+    #[clap(long, value_parser)]
+    since: Option<String>,
+    /// File listing the directories to analyze together, one per line and
+    /// relative to the file. Each directory's files are labelled with the
+    /// path to it
+    #[clap(long, value_parser)]
+    paths_from: Option<PathBuf>,
+    /// Print a file as the keyword matcher sees it, with the comments
+    /// stripped, for debugging the complexity estimates
+    #[clap(long, value_parser, hide = true)]
+    dump_stripped: Option<PathBuf>,
+    /// Root the labels at this ancestor of the analyzed path instead, so runs
+    /// on different paths share the same labels. Ignored for archives
+    #[clap(long, value_parser)]
+    relative_to: Option<PathBuf>,
+    /// Stop after analyzing this many files, warning that the results are
+    /// partial
+    #[clap(long, value_parser)]
+    max_files: Option<usize>,
+    /// Print the comment noting its complexity which --annotate-write would
+    /// add to the top of each file, instead of writing the treemap
+    #[clap(long, action)]
+    annotate: bool,
+    /// Add a comment noting its complexity to the top of each file, replacing
+    /// the one from an earlier run
+    #[clap(long, action)]
+    annotate_write: bool,
 }
 
-fn main()
+/// Default to as many threads as there is available parallelism
+fn default_threads() -> usize
 {
-    let args = Args::parse();
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
 
-    let walker = WalkDir::new(&args.path).into_iter();
+impl Args
+{
+    /// Whether a file has at least the --min-functions functions
+    fn has_enough_functions(&self, file: &FileResult) -> bool
+    {
+        file.has_functions(self.min_functions)
+    }
 
-    let mut nlocs = Vec::new();
-    let mut labels = Vec::new();
-    let mut parents = Vec::new();
-    let mut ccs = Vec::new();
+    /// Get the patterns for the paths to skip, with the test file patterns
+    /// first if tests are excluded
+    fn exclude_patterns(&self) -> Vec<String>
+    {
+        let mut patterns = Vec::new();
 
-    /* parse each file and calculate complexity */
-    for entry in walker.filter_entry(|e| !file_parser::is_hidden(e))
+        if self.exclude_tests && self.test_pattern.is_empty()
+        {
+            patterns.extend(exclude::DEFAULT_TEST_PATTERNS.iter().map(|p| p.to_string()));
+        }
+        else if self.exclude_tests
+        {
+            patterns.extend(self.test_pattern.iter().cloned());
+        }
+
+        patterns.extend(self.exclude.iter().cloned());
+
+        patterns
+    }
+
+    /// Get the version and the options which change the results of a file,
+    /// which a cache is only valid for
+    fn cache_fingerprint(&self, custom_keywords: &BTreeMap<String, Keywords>) -> String
     {
-        if file_parser::is_file_extension_valid(&entry.as_ref().unwrap()
-                                                      .file_name()
-                                                      .to_str().unwrap())
+        format!("{} logical_lines={} count_preprocessor={} count_returns={} count_asm={} algorithm={:?} keywords={:?}", env!("CARGO_PKG_VERSION"), self.logical_lines, self.count_preprocessor, self.count_returns, self.count_asm, self.algorithm, custom_keywords)
+    }
+
+    /// Get the keywords configured for extensions in the config file
+    fn custom_keywords(&self) -> Result<BTreeMap<String, Keywords>, Failed>
+    {
+        let path = match &self.config
         {
-            let mut file = FileParser::new(&entry.as_ref().unwrap());
+            Some(path) => path.clone(),
+            None if Path::new(CONFIG_FILE).is_file() => PathBuf::from(CONFIG_FILE),
+            None => return Ok(BTreeMap::new()),
+        };
 
-            match file.file_walk()
-            {
-                Ok(()) => {
-                    nlocs.push(file.nloc.unwrap());
-                    ccs.push(file.cc.unwrap());
-                    labels.push(file.label.unwrap().clone());
-                    parents.push(file.parent.unwrap().clone());
-                },
-                Err(e) => {
-                    eprintln!("Error: {:?}", e);
-                    continue;
-                }
+        match Config::load(&path)
+        {
+            Ok(config) => Ok(config.keywords()),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                Err(Failed)
             }
+        }
+    }
 
-            /* dumb to do this again but it works */
-            let depth = entry.as_ref().unwrap().depth();
-            let len = entry.as_ref().unwrap().path().to_str().unwrap()
-                           .split("/").count();
+    /// Get the canonical path the labels are relative to, checking that the
+    /// analyzed path is under it
+    fn relative_to(&self) -> Result<Option<PathBuf>, Failed>
+    {
+        let prefix = match &self.relative_to
+        {
+            Some(prefix) => prefix,
+            None => return Ok(None),
+        };
+        let canonical = |path: &Path| path.canonicalize().map_err(|e|
+        {
+            eprintln!("Error: unable to resolve {:?}: {}", path, e);
+            Failed
+        });
 
-            let mut full_path = entry.as_ref().unwrap().path().to_str().unwrap()
-                                     .split("/")
-                                     .collect::<Vec<&str>>();
+        let prefix = canonical(prefix)?;
 
-            /* pop to remove filename from path */
-            full_path.pop();
+        if !canonical(&self.path)?.starts_with(&prefix)
+        {
+            eprintln!("Error: {:?} is not under --relative-to {:?}", self.path, prefix);
+            return Err(Failed);
+        }
 
-            /* loop through and check if the parent dirs are in the parent and label vecs */
-            for _ in 0..depth
-            {
-                /* check if the path is a parent */
+        Ok(Some(prefix))
+    }
 
-                /* if the parent path does not exist in the parent vec */
-                if !labels.contains(&full_path[len-depth-1..].join("/"))
-                {
-                    nlocs.push(0);
-                    ccs.push(0.0);
-                    labels.push(full_path[len-depth-1..].join("/"));
+    /// Get the normalizer for the complexities of the files, if normalizing
+    fn normalizer(&self, files: &[FileResult]) -> Option<Normalizer>
+    {
+        self.normalize.then(|| Normalizer::new(files, self.normalize_max))
+    }
+
+    /// Get the JSON report of the files, with the normalized complexities and
+    /// the Halstead metrics if asked for
+    fn json_report(&self, files: &[FileResult], snippets: &[Snippet], options: &ParserOptions) -> JsonReport
+    {
+        let mut report = JsonReport::new(files, snippets);
+
+        if let Some(normalizer) = self.normalizer(files)
+        {
+            report = report.normalized(&normalizer);
+        }
+
+        if self.halstead
+        {
+            let metrics: Vec<Option<Halstead>> = files.iter()
+                                                      .map(|f| halstead::file_halstead(f, options))
+                                                      .collect();
+            report = report.with_halstead(&metrics);
+        }
+
+        report
+    }
+
+    /// Get the file listing the directories to analyze, if there is one
+    fn path_list(&self) -> Option<PathBuf>
+    {
+        match &self.paths_from
+        {
+            Some(list) => Some(list.clone()),
+            None => cyclo::is_path_list(&self.path).then(|| self.path.clone()),
+        }
+    }
+
+    /// Get the name displayed for the root of the treemap, if there is a
+    /// single root to rename
+    fn root_label(&self) -> Option<&str>
+    {
+        match self.group_by
+        {
+            GroupBy::Dir => self.root_label.as_deref(),
+            GroupBy::Language => None,
+        }
+    }
+
+    /// Get the options for the file parser from the arguments
+    fn parser_options(&self) -> Result<ParserOptions, Failed>
+    {
+        let custom_keywords = self.custom_keywords()?;
+        let exclude = match ExcludePatterns::new(&self.exclude_patterns())
+        {
+            Ok(exclude) => exclude,
+            Err(e) => {
+                eprintln!("Error: invalid --exclude pattern: {}", e);
+                return Err(Failed);
+            }
+        };
+
+        Ok(ParserOptions
+        {
+            logical_lines: self.logical_lines,
+            include_data: self.include_data,
+            include_markdown: self.include_markdown,
+            detect_shebang: self.detect_shebang,
+            languages: self.languages.clone(),
+            exclude_extensions: self.exclude_extensions.clone(),
+            count_preprocessor: self.count_preprocessor,
+            count_returns: self.count_returns,
+            count_asm: self.count_asm,
+            unhide: self.unhide.clone(),
+            timings: self.timings.then(|| Arc::new(Timings::default())),
+            errors: Some(Arc::new(Mutex::new(Vec::new()))),
+            cache: self.cache.as_ref().map(|path| Arc::new(Cache::load(path, &self.cache_fingerprint(&custom_keywords)))),
+            custom_keywords,
+            relative_to: self.relative_to()?,
+            max_files: self.max_files.map(|max| Arc::new(FileLimit::new(max))),
+            algorithm: self.algorithm,
+            exclude,
+        })
+    }
+}
+
+/// Print a histogram of the file complexities to stderr, so it isn't mixed
+/// into the output, binned into the buckets 0-5, 6-10, 11-20 and 21+
+fn print_histogram(files: &[FileResult])
+{
+    let buckets = [("0-5", 5.0), ("6-10", 10.0), ("11-20", 20.0), ("21+", f64::INFINITY)];
+    let mut counts = [0; 4];
+
+    for file in files
+    {
+        /* the last bucket is unbounded so this always finds one */
+        let index = buckets.iter().position(|(_, max)| file.cc <= *max).unwrap();
+        counts[index] += 1;
+    }
+
+    eprintln!("complexity histogram ({} files):", files.len());
+
+    for ((bucket, _), count) in buckets.iter().zip(counts)
+    {
+        eprintln!("{:>7}: {}", bucket, count);
+    }
+}
+
+/// Analyze the files under --path, printing the number of files parsed out of
+/// the number discovered to stderr as they finish
+fn analyze_with_progress(args: &Args, options: &ParserOptions) -> Vec<FileResult>
+{
+    /* the walk is lazy so it has to be done once up front to get the total */
+    let total = cyclo::walk(&args.path, options).take(args.max_files.unwrap_or(usize::MAX)).count();
+    let mut files = Vec::with_capacity(total);
+
+    for file in cyclo::analyze_iter(&args.path, options, args.threads)
+    {
+        files.push(file);
+        eprint!("\rparsed {}/{} files", files.len(), total);
+    }
+
+    eprintln!();
+
+    files.sort_by(|a, b| a.label.cmp(&b.label));
+
+    files
+}
+
+/// Print the `n` most complex files under their directories to stderr, with
+/// the mean complexity of each directory. The directory of the most complex
+/// file is first
+fn print_top(files: &[FileResult], n: usize)
+{
+    let color = term::color_enabled();
+    let mean = mean_complexity(files);
+    let totals = Treemap::from_files(files).dir_totals();
+
+    let mut top: Vec<&FileResult> = files.iter().collect();
+    top.sort_by(|a, b| b.cc.total_cmp(&a.cc).then_with(|| a.label.cmp(&b.label)));
+    top.truncate(n);
+
+    let mut dirs: Vec<&str> = Vec::new();
+
+    for file in &top
+    {
+        if !dirs.contains(&file.parent.as_str())
+        {
+            dirs.push(&file.parent);
+        }
+    }
+
+    eprintln!("{:>10} {:>8}  path", "cc", "nloc");
+
+    for dir in dirs
+    {
+        /* a single file passed to --path has no directory */
+        if !dir.is_empty()
+        {
+            let total = totals.get(dir).copied().unwrap_or_default();
+            eprintln!("{}", term::paint(&format!("{:>10.2} {:>8}  {}/", total.cc_mean(), total.nloc, dir), Color::Dim, color));
+        }
+
+        for file in top.iter().filter(|f| f.parent == dir)
+        {
+            let row = format!("{:>10.2} {:>8}    {}", file.cc, file.nloc, file.label);
+            eprintln!("{}", term::paint(&row, term::complexity_color(file.cc, mean), color));
+        }
+    }
+}
+
+/// Get the mean complexity of the files, or zero if there are none. Only the
+/// files are counted, not the directories synthesized for the treemap
+fn mean_complexity(files: &[FileResult]) -> f64
+{
+    if files.is_empty()
+    {
+        return 0.0;
+    }
+
+    files.iter().map(|f| f.cc).sum::<f64>() / files.len() as f64
+}
+
+/// Get the complexity above which a file is flagged. A relative threshold is
+/// a factor of the mean complexity of the files
+fn threshold(files: &[FileResult], args: &Args) -> f64
+{
+    match args.relative_threshold
+    {
+        Some(factor) => factor * mean_complexity(files),
+        None => args.threshold,
+    }
+}
+
+/// Print the files above the relative threshold to stderr, returning whether
+/// there were any
+fn check_relative_threshold(files: &[FileResult], factor: f64) -> bool
+{
+    let mean = mean_complexity(files);
+    let threshold = factor * mean;
+    let flagged: Vec<&FileResult> = files.iter().filter(|f| f.cc > threshold).collect();
+
+    for file in &flagged
+    {
+        eprintln!("{} has a complexity of {}, which is more than {}x the mean of {:.2}",
+                  file.label, file.cc, factor, mean);
+    }
+
+    !flagged.is_empty()
+}
+
+/// Print a one line summary like
+/// `cyclo: 420 files, mean cc 8.3, max cc 61 (src/parser.c)` to stderr. The
+/// first file in label order wins a tie for the most complex
+fn print_summary(files: &[FileResult])
+{
+    let max = files.iter().fold(None, |max: Option<&FileResult>, f| match max
+    {
+        Some(max) if max.cc >= f.cc => Some(max),
+        _ => Some(f),
+    });
+
+    match max
+    {
+        Some(max) => eprintln!("cyclo: {} files, mean cc {:.1}, max cc {} ({})", files.len(), mean_complexity(files), max.cc, max.label),
+        None => eprintln!("cyclo: 0 files"),
+    }
+}
+
+/// Print the files which failed to parse to stderr, grouped by the kind of
+/// failure
+fn print_errors(options: &ParserOptions)
+{
+    /* they're taken so a watch only prints the failures of each update */
+    let errors = match &options.errors
+    {
+        Some(errors) => std::mem::take(&mut *errors.lock().unwrap()),
+        None => return,
+    };
+
+    if errors.is_empty()
+    {
+        return;
+    }
+
+    let mut kinds: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+    for error in errors.iter()
+    {
+        kinds.entry(error.kind()).or_default().push(error.file());
+    }
+
+    eprintln!("{} files failed to parse:", errors.len());
+
+    for (kind, mut files) in kinds
+    {
+        files.sort_unstable();
+        eprintln!("  {}: {}", kind, files.join(", "));
+    }
+}
+
+/// Print the mean complexity of the treemap to stderr if it is above the
+/// maximum, returning whether it was
+fn check_max_mean(treemap: &Treemap, mode: MeanMode, max: f64) -> bool
+{
+    let mean = treemap.mean(mode);
+
+    if mean > max
+    {
+        eprintln!("The mean complexity of {:.2} is more than the maximum of {}", mean, max);
+    }
+
+    mean > max
+}
 
-                    full_path.pop();
+/// Get the source of the most complex functions, if asked for
+fn snippets(files: &[FileResult], args: &Args, options: &ParserOptions) -> Vec<Snippet>
+{
+    match args.snippets
+    {
+        Some(n) => functions::worst_functions(files, n, options),
+        None => Vec::new(),
+    }
+}
 
-                    if full_path.is_empty()
-                    {
-                        parents.push("".to_string());
+/// Read a baseline, failing if it can't be
+fn read_baseline(path: &Path) -> Result<JsonReport, Failed>
+{
+    cyclo::baseline::read_baseline(path).map_err(|e|
+    {
+        eprintln!("Error: {}", e);
+        Failed
+    })
+}
+
+/// Compare the files to a baseline as a ratchet, printing the files already
+/// in it whose complexity rose by more than the tolerance and whether the
+/// total did. Returns true if anything rose
+fn check_no_increase(path: &Path, files: &[FileResult], tolerance: f64) -> Result<bool, Failed>
+{
+    let baseline = read_baseline(path)?;
+    let increases = cyclo::baseline::increases(&baseline, files, tolerance);
+    let total = cyclo::baseline::total_increase(&baseline, files, tolerance);
+
+    for increase in &increases
+    {
+        eprintln!("{} went from a complexity of {} to {}", increase.label, increase.before.unwrap(), increase.after);
+    }
+
+    if let Some((before, after)) = total
+    {
+        eprintln!("the total complexity went from {} to {}", before, after);
+    }
+
+    Ok(!increases.is_empty() || total.is_some())
+}
+
+/// Compare the files to a baseline, printing the files whose complexity went
+/// up to stderr and returning whether there were any
+fn check_baseline(path: &Path, files: &[FileResult]) -> Result<bool, Failed>
+{
+    let baseline = read_baseline(path)?;
+    let regressions = cyclo::baseline::regressions(&baseline, files);
+
+    for regression in &regressions
+    {
+        match regression.before
+        {
+            Some(before) => eprintln!("{} went from a complexity of {} to {}", regression.label, before, regression.after),
+            None => eprintln!("{} is new with a complexity of {}", regression.label, regression.after),
+        }
+    }
+
+    Ok(!regressions.is_empty())
+}
+
+/// Print the number of files skipped because they can't be parsed, by
+/// extension, like: skipped 342 files: .go(210), .rs(132)
+fn print_skipped(args: &Args, options: &ParserOptions)
+{
+    let skipped = cyclo::skipped(&args.path, options);
+    let total: u64 = skipped.iter().map(|(_, count)| count).sum();
 
-                    }
-                    else
-                    {
-                        parents.push(full_path[len-depth-1..].join("/"));
-                    }
+    let extensions: Vec<String> = skipped.iter()
+                                         .map(|(extension, count)| format!("{}({})", extension, count))
+                                         .collect();
+
+    eprintln!("skipped {} files: {}", total, extensions.join(", "));
+}
+
+/// Print the time spent in each phase to stderr. The walk, complexity and
+/// nloc phases are summed over the worker threads, so they can add up to more
+/// than the time the analysis took
+fn print_timings(timings: &Timings, analysis: Duration, treemap: Duration, output: Duration)
+{
+    eprintln!("timings:");
+    eprintln!("{:>12}: {:?}", "walk", timings.get(Phase::Walk));
+    eprintln!("{:>12}: {:?}", "complexity", timings.get(Phase::Complexity));
+    eprintln!("{:>12}: {:?}", "nloc", timings.get(Phase::Nloc));
+    eprintln!("{:>12}: {:?}", "analysis", analysis);
+    eprintln!("{:>12}: {:?}", "treemap", treemap);
+    eprintln!("{:>12}: {:?}", "output", output);
+}
+
+/// What --path named, which decides what is printed about the files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Input
+{
+    /// A single file, whose result is also printed on its own line
+    File,
+    /// A directory, whose skipped files can be reported
+    Dir,
+    /// An archive or a list of directories
+    Other,
+}
+
+/// Analyze a single file passed to --path. There is no directory hierarchy
+/// to build, so the result is also printed to stdout and the treemap is a
+/// single node
+fn analyze_file(path: &Path, args: &Args) -> Result<(), Failed>
+{
+    let options = args.parser_options()?;
+
+    let start = Instant::now();
+    let file = match cyclo::analyze_file(path, &options)
+    {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return Err(Failed);
+        }
+    };
+    let analysis = start.elapsed();
+
+    if !args.has_enough_functions(&file)
+    {
+        eprintln!("Warning: {:?} has fewer than --min-functions functions, so there is nothing to analyze", path);
+        return Ok(());
+    }
+
+    report(vec![file], args, &options, Input::File, analysis)
+}
+
+/// Print the result of a single file, with its Halstead metrics if asked for
+fn print_file(file: &FileResult, args: &Args, options: &ParserOptions)
+{
+    let metrics = match args.halstead
+    {
+        true => halstead::file_halstead(file, options),
+        false => None,
+    };
+
+    match metrics
+    {
+        Some(h) => println!("file: {:?}, nloc: {:?}, cc: {:?}, volume: {:.2}, difficulty: {:.2}", file.label, file.nloc, file.cc, h.volume(), h.difficulty()),
+        None => println!("file: {:?}, nloc: {:?}, cc: {:?}", file.label, file.nloc, file.cc),
+    }
+}
+
+/// Read the directories in a path list, failing if the list can't be read
+/// or any of them isn't a directory
+fn read_path_list(list: &Path) -> Result<Vec<PathBuf>, Failed>
+{
+    let dirs = match cyclo::read_path_list(list)
+    {
+        Ok(dirs) => dirs,
+        Err(e) => {
+            eprintln!("Error: unable to read {:?}: {}", list, e);
+            return Err(Failed);
+        }
+    };
+
+    if let Some(dir) = dirs.iter().find(|d| !d.is_dir())
+    {
+        eprintln!("Error: {:?} listed in {:?} is not a directory", dir, list);
+        return Err(Failed);
+    }
+
+    Ok(dirs)
+}
+
+/// Print the files which would be analyzed, without parsing them
+fn dry_run(args: &Args) -> Result<(), Failed>
+{
+    let options = args.parser_options()?;
+    let paths: Vec<PathBuf> = match args.path_list()
+    {
+        Some(list) => read_path_list(&list)?.iter()
+                                           .flat_map(|dir| cyclo::walk(dir, &options))
+                                           .map(|e| e.into_path())
+                                           .take(args.max_files.unwrap_or(usize::MAX))
+                                           .collect(),
+        None if args.path.is_file() => {
+            let name = args.path.file_name().unwrap_or_default().to_string_lossy();
+            file_parser::is_file_valid(&name, &options).then(|| args.path.clone()).into_iter().collect()
+        },
+        None => cyclo::walk(&args.path, &options).map(|e| e.into_path()).take(args.max_files.unwrap_or(usize::MAX)).collect(),
+    };
+
+    for path in &paths
+    {
+        println!("{}", path.display());
+    }
+
+    println!("{} files would be analyzed", paths.len());
+
+    Ok(())
+}
+
+/// Analyze the path repeatedly, printing the statistics of the times taken
+fn bench_self(args: &Args, runs: usize) -> Result<(), Failed>
+{
+    let mut times = Vec::with_capacity(runs);
+    let mut files = 0;
+
+    for _ in 0..runs
+    {
+        /* each run has its own --max-files limit */
+        let options = ParserOptions { cache: None, ..args.parser_options()? };
+        let start = Instant::now();
+        files = cyclo::analyze(&args.path, &options, args.threads).len();
+        times.push(start.elapsed());
+    }
+
+    match timings::run_stats(&times)
+    {
+        Some(stats) => println!("analyzed {} files {} times: min {:?}, median {:?}, mean {:?}, max {:?}, stddev {:?}",
+                                files, runs, stats.min, stats.median, stats.mean, stats.max, stats.stddev),
+        None => println!("no runs"),
+    }
+
+    Ok(())
+}
+
+/// Print the lines of a file which are searched for keywords
+fn dump_stripped(path: &Path, args: &Args) -> Result<(), Failed>
+{
+    let content = match fs::read(path)
+    {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Error: unable to read {:?}: {}", path, e);
+            return Err(Failed);
+        }
+    };
+
+    let filename = path.file_name().unwrap_or_default().to_string_lossy();
+
+    match file_parser::stripped_source(&filename, &String::from_utf8_lossy(&content), &args.parser_options()?)
+    {
+        Some(lines) => lines.iter().for_each(|line| println!("{}", line)),
+        None => {
+            eprintln!("Error: {:?} isn't in a supported language", path);
+            return Err(Failed);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the change in complexity introduced by each file in a diff
+fn analyze_diff(diff: &Path, args: &Args) -> Result<(), Failed>
+{
+    let diff = match fs::read_to_string(diff)
+    {
+        Ok(diff) => diff,
+        Err(e) => {
+            eprintln!("Error: unable to read {:?}: {}", diff, e);
+            return Err(Failed);
+        }
+    };
+
+    let results = cyclo::diff::analyze_diff(&diff, &args.parser_options()?);
+
+    for result in &results
+    {
+        println!("file: {:?}, added: {:?}, removed: {:?}, delta: {:+?}",
+                 result.file, result.added, result.removed, result.delta());
+    }
+
+    println!("total delta: {:+?}", results.iter().map(|r| r.delta()).sum::<f64>());
+
+    Ok(())
+}
+
+/// Print the annotation for each file, writing it to the top of the file if
+/// asked to
+fn annotate(files: &[FileResult], args: &Args, options: &ParserOptions)
+{
+    for file in files
+    {
+        let annotation = match args.annotate_write
+        {
+            true => cyclo::annotate::annotate_file(file, options),
+            false => Ok(cyclo::annotate::annotation(file, options)),
+        };
+
+        match annotation
+        {
+            Ok(Some(annotation)) => println!("{}: {}", file.label, annotation),
+            Ok(None) => {},
+            Err(e) => eprintln!("Error: unable to annotate {:?}: {}", file.path, e),
+        }
+    }
+}
+
+/// Write the analysis of a directory in the output format, or to the output
+/// directory
+fn write_output(files: &[FileResult], treemap: &Treemap, args: &Args, options: &ParserOptions) -> Result<(), Failed>
+{
+    if args.summary_json
+    {
+        println!("{}", output::summary_json(files, args.pretty));
+    }
+    else if let Some(dir) = &args.output_dir
+    {
+        let snippets = snippets(files, args, options);
+
+        let js = output::js(treemap, args.root_label(), &snippets, args.mean_mode, args.accessible);
+
+        if let Err(e) = output::write_output_dir(dir, &js, &args.json_report(files, &snippets, options), args.js_module, args.pretty)
+        {
+            eprintln!("Error: unable to write to {:?}: {}", dir, e);
+            return Err(Failed);
+        }
+
+        if args.validate
+        {
+            validate_file(&dir.join("cyclo.js"), validate::validate_js)?;
+            validate_file(&dir.join("cyclo.json"), validate::validate_json)?;
+        }
+    }
+    else
+    {
+        match args.format
+        {
+            Format::Js => {
+                output::write_js(treemap, args.root_label(), &snippets(files, args, options), args.mean_mode, args.accessible, args.js_module);
+
+                if args.validate
+                {
+                    validate_file(Path::new(output::JS_PATH), validate::validate_js)?;
                 }
+            },
+            Format::Sarif => println!("{}", output::sarif(files, threshold(files, args))),
+            Format::Junit => println!("{}", output::junit(files, threshold(files, args))),
+            Format::Json => println!("{}", validated_json(output::json(&args.json_report(files, &snippets(files, args, options), options), args.pretty), args)?),
+            Format::Dot => println!("{}", output::dot(treemap, args.root_label())),
+            Format::NdjsonSummary => println!("{}", output::ndjson_summary(treemap, args.root_label())),
+        }
+    }
+
+    Ok(())
+}
+
+/// Read back a file which was written and check it's well formed, failing
+/// if it isn't
+fn validate_file(path: &Path, validate: fn(&str) -> Result<(), ValidateError>) -> Result<(), Failed>
+{
+    let content = match fs::read_to_string(path)
+    {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Error: unable to read {:?} to validate it: {}", path, e);
+            return Err(Failed);
+        }
+    };
+
+    if let Err(e) = validate(&content)
+    {
+        eprintln!("Error: {:?} is broken: {}", path, e);
+        return Err(Failed);
+    }
+
+    Ok(())
+}
+
+/// Check the JSON report is well formed before it's printed if asked to,
+/// failing if it isn't
+fn validated_json(json: String, args: &Args) -> Result<String, Failed>
+{
+    if args.validate
+    {
+        if let Err(e) = validate::validate_json(&json)
+        {
+            eprintln!("Error: the JSON output is broken: {}", e);
+            return Err(Failed);
+        }
+    }
+
+    Ok(json)
+}
+
+/// Watch a directory after the first run, parsing only the files which
+/// changed and updating them in the results and the treemap before writing
+/// the output again. Only returns if the output can't be written, the watch
+/// is stopped with ctrl-c
+fn watch(mut files: Vec<FileResult>, mut treemap: Treemap, args: &Args, options: &ParserOptions) -> Result<(), Failed>
+{
+    let mut snapshot = cyclo::watch::Snapshot::new(&args.path, options);
+
+    eprintln!("watching {:?} for changes, press ctrl-c to stop", args.path);
+
+    loop
+    {
+        thread::sleep(WATCH_INTERVAL);
+
+        let next = snapshot.refresh(&args.path, options);
+        let changes = cyclo::watch::changes(&snapshot.files, &next.files);
+        snapshot = next;
+
+        if changes.is_empty()
+        {
+            continue;
+        }
+
+        for path in &changes.removed
+        {
+            if let Some(i) = files.iter().position(|f| f.path == *path)
+            {
+                treemap.remove_file(&files.remove(i).label, args.dir_rollup);
             }
         }
+
+        let relative: Vec<PathBuf> = changes.changed.iter()
+                                                    .filter_map(|p| p.strip_prefix(&args.path).ok())
+                                                    .map(Path::to_path_buf)
+                                                    .collect();
+
+        for file in cyclo::analyze_paths(&args.path, &relative, options)
+        {
+            cyclo::watch::update(&mut files, &mut treemap, file, args.min_functions, args.dir_rollup);
+        }
+
+        if let Some(key) = args.sort
+        {
+            treemap.sort(key);
+        }
+
+        print_errors(options);
+
+        match treemap.is_empty()
+        {
+            true => eprintln!("Warning: there are no files left to analyze"),
+            false => write_output(&files, &treemap, args, options)?,
+        }
+
+        eprintln!("updated {} changed and {} removed files", changes.changed.len(), changes.removed.len());
+    }
+}
+
+/// Print the files ranked by their complexity multiplied by their number of
+/// commits in the churn window
+fn print_hotspots(files: &[FileResult], args: &Args) -> Result<(), Failed>
+{
+    /* git is run in the directory of a single file */
+    let dir = match args.path.is_file()
+    {
+        true => args.path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")),
+        false => &args.path,
+    };
+
+    let churn = match cyclo::hotspots::churn(dir, &args.churn_window)
+    {
+        Ok(churn) => churn,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return Err(Failed);
+        }
+    };
+
+    for hotspot in cyclo::hotspots::hotspots(files, &churn)
+    {
+        println!("{:>10.2}  cc: {:<8.2} commits: {:<6} {}", hotspot.score, hotspot.cc, hotspot.commits, hotspot.label);
     }
 
-    /* test lengths of the vecs, since they must all be the same */
-    assert_eq!(nlocs.len(), labels.len(), "nloc ({}) and label ({}) vector length equality failed", nlocs.len(), labels.len());
-    assert_eq!(labels.len(), parents.len(), "labels ({}) and parents ({}) vector length equality failed", labels.len(), parents.len());
-    assert_eq!(parents.len(), ccs.len(), "parents ({}) and ccs ({}) vector lengthe equality failed", parents.len(), ccs.len());
+    Ok(())
+}
+
+/// Run the analysis, so that everything it holds, like the remote clone, is
+/// dropped before exiting
+fn run() -> Result<(), Failed>
+{
+    let mut args = Args::parse();
 
+    if let Some(diff) = &args.diff
+    {
+        return analyze_diff(diff, &args);
+    }
 
-    /* write the js file */
+    if let Some(path) = &args.dump_stripped
     {
-        let sum = ccs.iter().sum::<f64>();
-        let count = ccs.len();
+        return dump_stripped(path, &args);
+    }
 
-        assert!(count > 0, "count ({}) is not greater than zero", count);
+    /* the clone is deleted once it is dropped at the end of the run */
+    let remote = match cyclo::remote::is_remote(&args.path)
+    {
+        true => match RemoteClone::new(&args.path.to_string_lossy())
+        {
+            Ok(remote) => Some(remote),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return Err(Failed);
+            }
+        },
+        false => None,
+    };
 
-        let mean = sum / count as f64;
+    if let Some(remote) = &remote
+    {
+        args.path = remote.path.clone();
+    }
 
-        let js_file = format!(r#"
-var jsondata = [{{
-        type: "treemap",
-        values: {:?},
-        labels: {:?},
-        parents: {:?},
-        marker: {{colors: {:.2?}, cmid: {:.2?}, colorscale: "Blues"}}
-}}]
-    "#, nlocs, labels, parents, ccs, mean);
+    let is_archive = cyclo::archive::is_archive(&args.path);
 
-        fs::write("html/scripts/cyclo.js", js_file).unwrap();
+    if args.annotate_write && is_archive
+    {
+        eprintln!("Error: the files in an archive can't be annotated");
+        return Err(Failed);
     }
 
+    if is_archive && (args.snippets.is_some() || args.functions || args.halstead)
+    {
+        eprintln!("Error: --snippets, --functions and --halstead read the files again, which they can't in an archive");
+        return Err(Failed);
+    }
+
+    if args.dry_run
+    {
+        if is_archive
+        {
+            eprintln!("Error: --dry-run can't list the files in an archive");
+            return Err(Failed);
+        }
+
+        return dry_run(&args);
+    }
+
+    if let Some(runs) = args.bench_self
+    {
+        if is_archive || args.path.is_file()
+        {
+            eprintln!("Error: --bench-self needs a directory to analyze");
+            return Err(Failed);
+        }
+
+        return bench_self(&args, runs);
+    }
+
+    let path_list = args.path_list();
+
+    if args.watch && (is_archive || args.path.is_file() || path_list.is_some() || args.group_by != GroupBy::Dir)
+    {
+        eprintln!("Error: --watch needs a directory to analyze, grouped by directory");
+        return Err(Failed);
+    }
+
+    if args.path.is_file() && !is_archive && path_list.is_none()
+    {
+        return analyze_file(&args.path, &args);
+    }
+
+    let options = args.parser_options()?;
+
+    let start = Instant::now();
+    let files = if is_archive
+    {
+        match cyclo::archive::analyze_archive(&args.path, &options)
+        {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return Err(Failed);
+            }
+        }
+    }
+    else if let Some(list) = &path_list
+    {
+        let base = list.parent().unwrap_or_else(|| Path::new(""));
+
+        cyclo::analyze_dirs(base, &read_path_list(list)?, &options, args.threads)
+    }
+    else if let Some(reference) = &args.since
+    {
+        match cyclo::hotspots::changed(&args.path, reference)
+        {
+            Ok(changed) => cyclo::analyze_paths(&args.path, &changed, &options),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return Err(Failed);
+            }
+        }
+    }
+    else if args.progress && io::stderr().is_terminal()
+    {
+        analyze_with_progress(&args, &options)
+    }
+    else
+    {
+        cyclo::analyze(&args.path, &options, args.threads)
+    };
+    let analysis = start.elapsed();
+
+    if options.max_files.as_ref().is_some_and(|l| l.is_truncated())
+    {
+        eprintln!("Warning: stopped after --max-files {} files, so the results are partial", files.len());
+    }
+
+    let files: Vec<FileResult> = files.into_iter()
+                                      .filter(|f| args.has_enough_functions(f))
+                                      .collect();
+
+    let input = match is_archive || path_list.is_some()
+    {
+        true => Input::Other,
+        false => Input::Dir,
+    };
+
+    report(files, &args, &options, input, analysis)
+}
+
+/// Report the analyzed files, then run the checks which can fail the run.
+/// Everything after the analysis is done here, so a single file and a
+/// directory are reported the same way
+fn report(files: Vec<FileResult>, args: &Args, options: &ParserOptions, input: Input, analysis: Duration) -> Result<(), Failed>
+{
+    print_errors(options);
+
+    if args.annotate || args.annotate_write
+    {
+        annotate(&files, args, options);
+        return Ok(());
+    }
+
+    if args.hotspots
+    {
+        return print_hotspots(&files, args);
+    }
+
+    let start = Instant::now();
+
+    /* the directory nodes are only synthesized for the files left. the
+     * files are filtered on their raw complexity, but normalized relative to
+     * all of them. the deep files are collapsed last */
+    let normalizer = args.normalizer(&files);
+    let visible: Vec<FileResult> = files.iter()
+                                        .filter(|f| args.min_cc.is_none_or(|min_cc| f.cc >= min_cc))
+                                        .map(|f| FileResult { cc: normalizer.map_or(f.cc, |n| n.apply(f.cc)), ..f.clone() })
+                                        .collect();
+    let visible = match args.treemap_depth
+    {
+        Some(depth) => treemap::collapse_files(&visible, depth),
+        None => visible,
+    };
+    let mut treemap = Treemap::from_files_grouped(&visible, args.group_by);
+
+    treemap.rollup(args.dir_rollup);
+
+    if let Some(key) = args.sort
+    {
+        treemap.sort(key);
+    }
+
+    /* the functions are only drawn, they aren't in the directory totals */
+    if args.functions && (args.output_dir.is_some() || args.format != Format::NdjsonSummary)
+    {
+        let functions: Vec<Snippet> = files.iter()
+                                           .flat_map(|f| functions::file_snippets(f, options))
+                                           .map(|s| Snippet { cc: normalizer.map_or(s.cc, |n| n.apply(s.cc)), ..s })
+                                           .collect();
+        treemap.add_functions(&functions, options);
+    }
+    let treemap_time = start.elapsed();
+
+    let start = Instant::now();
+    if input == Input::File && args.format == Format::Js && !args.summary_json && args.output_dir.is_none()
+    {
+        print_file(&files[0], args, options);
+    }
+
+    /* like when watching, there's nothing to draw if --min-cc left nothing */
+    match treemap.is_empty()
+    {
+        true => eprintln!("Warning: there are no files left to analyze"),
+        false => write_output(&files, &treemap, args, options)?,
+    }
+    let output_time = start.elapsed();
+
+    if args.summary
+    {
+        print_summary(&files);
+    }
 
     if args.debug
     {
         /* write the debug file */
         let mut buffer = fs::File::create("debug.txt").unwrap();
 
-        for i in 0..nlocs.len()
+        for i in 0..treemap.len()
         {
-            writeln!(&mut buffer, "file: {:?}, nloc: {:?}, cc: {:?}", labels[i], nlocs[i], ccs[i]).unwrap();
+            writeln!(&mut buffer, "file: {:?}, nloc: {:?}, cc: {:?}", treemap.labels[i], treemap.nlocs[i], treemap.ccs[i]).unwrap();
         }
     }
+
+    if args.histogram
+    {
+        print_histogram(&files);
+    }
+
+    if let Some(n) = args.top
+    {
+        print_top(&files, n);
+    }
+
+    if args.report_skipped && input == Input::Dir
+    {
+        print_skipped(args, options);
+    }
+
+    if let Some(timings) = &options.timings
+    {
+        print_timings(timings, analysis, treemap_time, output_time);
+    }
+
+    if let (Some(cache), Some(path)) = (&options.cache, &args.cache)
+    {
+        if let Err(e) = cache.save(path)
+        {
+            eprintln!("Error: unable to write the cache '{}': {}", path.display(), e);
+        }
+    }
+
+    if let Some(path) = &args.write_baseline
+    {
+        if let Err(e) = cyclo::baseline::write_baseline(path, &files)
+        {
+            eprintln!("Error: {}", e);
+            return Err(Failed);
+        }
+    }
+
+    if args.watch
+    {
+        return watch(files, treemap, args, options);
+    }
+
+    let mut failed = false;
+
+    if let Some(factor) = args.relative_threshold
+    {
+        failed |= check_relative_threshold(&files, factor);
+    }
+
+    if let Some(max) = args.max_mean
+    {
+        failed |= check_max_mean(&treemap, args.mean_mode, max);
+    }
+
+    if let Some(path) = &args.baseline
+    {
+        failed |= match args.assert_no_increase
+        {
+            true => check_no_increase(path, &files, args.tolerance)?,
+            false => check_baseline(path, &files)?,
+        };
+    }
+
+    match failed
+    {
+        true => Err(Failed),
+        false => Ok(()),
+    }
+}
+
+fn main() -> ExitCode
+{
+    match run()
+    {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failed) => ExitCode::FAILURE,
+    }
 }
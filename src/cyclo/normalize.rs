@@ -0,0 +1,52 @@
+use crate::file_parser::FileResult;
+
+
+/// Maps complexities onto a fixed scale from 0 to 100, so the colorscales of
+/// runs over codebases of different sizes can be compared
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Normalizer
+{
+    /// Complexity mapped to 0
+    min: f64,
+    /// Complexity mapped to 100
+    max: f64,
+}
+
+impl Normalizer
+{
+    /// Scale from the least to the most complex of the files, or from zero
+    /// to a reference maximum which is the same across runs. Complexities
+    /// above the reference maximum are capped at 100
+    pub fn new(files: &[FileResult], reference_max: Option<f64>) -> Normalizer
+    {
+        match reference_max
+        {
+            Some(max) => Normalizer { min: 0.0, max },
+            None => Normalizer
+            {
+                min: files.iter().map(|f| f.cc).fold(f64::INFINITY, f64::min),
+                max: files.iter().map(|f| f.cc).fold(f64::NEG_INFINITY, f64::max),
+            },
+        }
+    }
+
+    /// Map a complexity onto the scale. Every complexity is 0 if the scale
+    /// is empty, like when all the files are as complex
+    pub fn apply(&self, cc: f64) -> f64
+    {
+        if self.max <= self.min
+        {
+            return 0.0;
+        }
+
+        ((cc - self.min) / (self.max - self.min) * 100.0).clamp(0.0, 100.0)
+    }
+
+    /// Get copies of the files with their complexities normalized
+    pub fn files(&self, files: &[FileResult]) -> Vec<FileResult>
+    {
+        files.iter()
+             .map(|f| FileResult { cc: self.apply(f.cc), ..f.clone() })
+             .collect()
+    }
+}
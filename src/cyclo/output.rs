@@ -0,0 +1,583 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::file_parser::FileResult;
+use crate::functions::Snippet;
+use crate::halstead::Halstead;
+use crate::normalize::Normalizer;
+use crate::treemap::{MeanMode, Treemap};
+
+
+/// Default complexity above which a file is reported as too complex
+pub const DEFAULT_THRESHOLD: f64 = 20.0;
+
+/// Version of the JSON output. This is bumped whenever a field is removed or
+/// its meaning changes, so readers can reject output they don't understand
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Where the treemap data read by the webserver's dashboard is written
+pub const JS_PATH: &str = "html/scripts/cyclo.js";
+
+/// Rule id used for files above the threshold in the SARIF log
+const SARIF_RULE_ID: &str = "cyclo/high-complexity";
+
+/// The format the analysis results are written in
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format
+{
+    /// plotly.js treemap data read by the webserver
+    Js,
+    /// SARIF 2.1.0 log for code scanning
+    Sarif,
+    /// JUnit XML report with a test case for each file, which fails if the
+    /// file is above the threshold
+    Junit,
+    /// JSON array of the parsed files
+    Json,
+    /// Graphviz DOT graph of the file hierarchy
+    Dot,
+    /// One JSON record per line for each directory, with the totals of the
+    /// files below it
+    NdjsonSummary,
+}
+
+/// Get the extra text shown when hovering over each node of the treemap,
+/// which is the language of the files
+fn hover_text(treemap: &Treemap) -> Vec<String>
+{
+    treemap.languages.iter()
+                     .map(|language| match language.is_empty()
+                     {
+                         true => String::new(),
+                         false => format!("language: {}", language),
+                     })
+                     .collect()
+}
+
+/// Build the treemap data read by the dashboard. The labels are used as the
+/// ids linking the nodes to their parents, so the displayed root label can be
+/// changed without breaking the hierarchy. Any snippets are shown below the
+/// treemap. The accessible colorscale is colorblind safe, and plotly picks a
+/// text color contrasting with each box since none is set
+pub fn js(treemap: &Treemap, root_label: Option<&str>, snippets: &[Snippet], mean_mode: MeanMode, accessible: bool) -> String
+{
+    let count = treemap.len();
+
+    assert!(count > 0, "count ({}) is not greater than zero", count);
+
+    let mean = treemap.mean(mean_mode);
+    let colorscale = match accessible
+    {
+        true => r##""Cividis", line: {color: "#ffffff", width: 1}"##,
+        false => r#""Blues""#,
+    };
+
+    format!(r#"
+var jsondata = [{{
+        type: "treemap",
+        values: {:?},
+        ids: {:?},
+        labels: {:?},
+        parents: {:?},
+        hovertext: {:?},
+        marker: {{colors: {:.2?}, cmid: {:.2?}, colorscale: {}}}
+}}]
+var snippets = {}
+    "#, treemap.nlocs, treemap.labels, treemap.display_labels(root_label), treemap.parents, hover_text(treemap), treemap.ccs, mean, colorscale,
+        serde_json::to_string(snippets).unwrap())
+}
+
+/// Turn the treemap data into an ES module exporting jsondata and snippets,
+/// so it can be imported into another page rather than assigning globals
+pub fn js_module(js: &str) -> String
+{
+    js.replacen("\nvar jsondata = ", "\nexport const jsondata = ", 1)
+      .replacen("\nvar snippets = ", "\nexport const snippets = ", 1)
+}
+
+/// Write the treemap data to the js file read by the webserver, or as an ES
+/// module, which the webserver's dashboard can't read
+pub fn write_js(treemap: &Treemap, root_label: Option<&str>, snippets: &[Snippet], mean_mode: MeanMode, accessible: bool, module: bool)
+{
+    let js = js(treemap, root_label, snippets, mean_mode, accessible);
+
+    fs::write(JS_PATH, match module { true => js_module(&js), false => js }).unwrap();
+}
+
+/// Build a standalone copy of the dashboard with the treemap data inlined.
+/// plotly.js is too big to inline so it is loaded from its CDN
+pub fn html(js: &str) -> String
+{
+    /* a label containing </script> would end the inline script early */
+    let js = js.replace("</", "<\\/");
+
+    format!(r#"<!DOCTYPE html>
+<html lang="en">
+    <head>
+        <title>cyclomatic complexity visualizer</title>
+        <style>
+{style}
+        </style>
+        <script type="text/javascript" src="https://cdn.plot.ly/plotly-2.14.0.min.js"></script>
+        <meta charset="UTF-8"/>
+    </head>
+    <body>
+        <h1>cyclomatic complexity treemap</h1>
+        <p class="note">[using plotly.js]</p>
+
+        <div id="plot"></div>
+        <div id="snippets"></div>
+    </body>
+    <script type="text/javascript">
+{js}
+    </script>
+    <script type="text/javascript">
+{plot}
+    </script>
+</html>
+"#, style=include_str!("../../html/static/style.css"), js=js, plot=include_str!("../../html/scripts/plot.js"))
+}
+
+/// Write the treemap data, the JSON report, and the standalone dashboard to
+/// a directory as cyclo.js, cyclo.json and index.html, creating it if needed.
+/// Only cyclo.js is written as a module, the dashboard inlines the globals.
+/// The JSON report is indented if it's pretty
+pub fn write_output_dir(dir: &Path, js: &str, report: &JsonReport, module: bool, pretty: bool) -> io::Result<()>
+{
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join("cyclo.js"), match module { true => js_module(js), false => js.to_string() })?;
+    fs::write(dir.join("cyclo.json"), json(report, pretty))?;
+    fs::write(dir.join("index.html"), html(js))
+}
+
+/// Relative paths are used as-is for the artifact uri, absolute paths need
+/// to be file uris
+fn artifact_uri(path: &Path) -> String
+{
+    let path = path.to_string_lossy();
+
+    if path.starts_with('/')
+    {
+        format!("file://{}", path)
+    }
+    else
+    {
+        path.trim_start_matches("./").to_string()
+    }
+}
+
+/// Build a SARIF 2.1.0 log where every file with a complexity above the
+/// threshold is a result. Files at or below the threshold are left out
+pub fn sarif(files: &[FileResult], threshold: f64) -> String
+{
+    let results: Vec<_> = files.iter()
+                               .filter(|f| f.cc > threshold)
+                               .map(|f| json!({
+                                   "ruleId": SARIF_RULE_ID,
+                                   "level": "warning",
+                                   "message": {
+                                       "text": format!("{} has a complexity of {} over {} lines of code, which exceeds the threshold of {}",
+                                                       f.label, f.cc, f.nloc, threshold)
+                                   },
+                                   "locations": [{
+                                       "physicalLocation": {
+                                           "artifactLocation": {
+                                               "uri": artifact_uri(&f.path)
+                                           },
+                                           "region": { "startLine": 1 }
+                                       }
+                                   }]
+                               }))
+                               .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cyclo",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [{
+                        "id": SARIF_RULE_ID,
+                        "shortDescription": {
+                            "text": "File complexity exceeds the threshold"
+                        }
+                    }]
+                }
+            },
+            "results": results
+        }]
+    });
+
+    serde_json::to_string_pretty(&log).unwrap()
+}
+
+/// Escape a string for an XML attribute. Control characters aren't allowed
+/// in XML at all, so they are replaced
+fn xml_escape(s: &str) -> String
+{
+    s.chars()
+     .map(|c| match c
+     {
+         '&' => "&amp;".to_string(),
+         '<' => "&lt;".to_string(),
+         '>' => "&gt;".to_string(),
+         '"' => "&quot;".to_string(),
+         '\'' => "&apos;".to_string(),
+         '\t' | '\n' | '\r' => c.to_string(),
+         c if c.is_control() => '\u{fffd}'.to_string(),
+         c => c.to_string(),
+     })
+     .collect()
+}
+
+/// Build a JUnit XML report where every file is a test case, which fails if
+/// its complexity is above the threshold
+pub fn junit(files: &[FileResult], threshold: f64) -> String
+{
+    let failures = files.iter().filter(|f| f.cc > threshold).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<testsuites name=\"cyclo\" tests=\"{}\" failures=\"{}\">\n", files.len(), failures));
+    xml.push_str(&format!("    <testsuite name=\"complexity\" tests=\"{}\" failures=\"{}\">\n", files.len(), failures));
+
+    for file in files
+    {
+        let case = format!("        <testcase classname=\"{}\" name=\"{}\"", xml_escape(&file.parent), xml_escape(&file.label));
+
+        match file.cc > threshold
+        {
+            true => {
+                let message = format!("{} has a complexity of {} over {} lines of code, which exceeds the threshold of {}",
+                                      file.label, file.cc, file.nloc, threshold);
+
+                xml.push_str(&format!("{}>\n            <failure type=\"complexity\" message=\"{}\"/>\n        </testcase>\n", case, xml_escape(&message)));
+            },
+            false => xml.push_str(&format!("{}/>\n", case)),
+        }
+    }
+
+    xml.push_str("    </testsuite>\n</testsuites>");
+
+    xml
+}
+
+/// A parsed file in the JSON output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonFile
+{
+    /// Path to the file from the root
+    pub label: String,
+    /// Label of the directory containing the file
+    pub parent: String,
+    /// Number of lines of code
+    pub nloc: u64,
+    /// Estimated complexity
+    pub cc: f64,
+    /// Number of functions
+    pub functions: u64,
+    /// The language the file was detected as, like c or py
+    #[serde(default)]
+    pub language: String,
+    /// Complexity on a scale from 0 to 100, if normalized
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized_cc: Option<f64>,
+    /// Halstead volume, if asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub halstead_volume: Option<f64>,
+    /// Halstead difficulty, if asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub halstead_difficulty: Option<f64>,
+}
+
+/// Totals over all the files in the JSON output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSummary
+{
+    /// Number of files parsed
+    pub files: u64,
+    /// Total lines of code
+    pub nloc: u64,
+    /// Total complexity
+    pub cc: f64,
+    /// Mean complexity of the files, or zero if there are none
+    pub mean_cc: f64,
+    /// Mean complexity of the files weighted by their lines of code, or zero
+    /// if there are no lines of code
+    #[serde(default)]
+    pub weighted_mean_cc: f64,
+}
+
+/// The JSON output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonReport
+{
+    /// Always JSON_SCHEMA_VERSION when written by this version of cyclo
+    pub schema_version: u32,
+    pub files: Vec<JsonFile>,
+    pub summary: JsonSummary,
+    /// The source of the most complex functions, if asked for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<Snippet>,
+}
+
+impl JsonReport
+{
+    pub fn new(files: &[FileResult], snippets: &[Snippet]) -> JsonReport
+    {
+        let files: Vec<JsonFile> = files.iter()
+                                        .map(|f| JsonFile
+                                        {
+                                            label: f.label.clone(),
+                                            parent: f.parent.clone(),
+                                            nloc: f.nloc,
+                                            cc: f.cc,
+                                            functions: f.functions,
+                                            language: f.language.clone(),
+                                            normalized_cc: None,
+                                            halstead_volume: None,
+                                            halstead_difficulty: None,
+                                        })
+                                        .collect();
+
+        let cc = files.iter().map(|f| f.cc).sum::<f64>();
+        let nloc = files.iter().map(|f| f.nloc).sum::<u64>();
+        let weighted_cc = files.iter().map(|f| f.cc * f.nloc as f64).sum::<f64>();
+
+        let summary = JsonSummary
+        {
+            files: files.len() as u64,
+            nloc,
+            cc,
+            mean_cc: if files.is_empty() { 0.0 } else { cc / files.len() as f64 },
+            weighted_mean_cc: if nloc == 0 { 0.0 } else { weighted_cc / nloc as f64 },
+        };
+
+        JsonReport { schema_version: JSON_SCHEMA_VERSION, files, summary, snippets: snippets.to_vec() }
+    }
+
+    /// Add the normalized complexity of each file alongside the raw one
+    pub fn normalized(mut self, normalizer: &Normalizer) -> JsonReport
+    {
+        for file in &mut self.files
+        {
+            file.normalized_cc = Some(normalizer.apply(file.cc));
+        }
+
+        self
+    }
+
+    /// Add the Halstead volume and difficulty of each file, in the same order
+    /// as the files. A file without them is left as it is
+    pub fn with_halstead(mut self, metrics: &[Option<Halstead>]) -> JsonReport
+    {
+        for (file, halstead) in self.files.iter_mut().zip(metrics)
+        {
+            file.halstead_volume = halstead.map(|h| h.volume());
+            file.halstead_difficulty = halstead.map(|h| h.difficulty());
+        }
+
+        self
+    }
+}
+
+/// Serialize a value as compact JSON, or indented if it is to be read by a
+/// person
+fn to_json<T: Serialize>(value: &T, pretty: bool) -> String
+{
+    match pretty
+    {
+        true => serde_json::to_string_pretty(value).unwrap(),
+        false => serde_json::to_string(value).unwrap(),
+    }
+}
+
+/// Serialize the versioned JSON report of the parsed files
+pub fn json(report: &JsonReport, pretty: bool) -> String
+{
+    to_json(report, pretty)
+}
+
+/// Aggregates over the files of one language in the JSON summary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonLanguageSummary
+{
+    /// Number of files in the language
+    pub files: u64,
+    /// Total lines of code
+    pub nloc: u64,
+    /// Mean complexity of the files
+    pub mean_cc: f64,
+    /// Highest complexity of the files
+    pub max_cc: f64,
+}
+
+/// The headline numbers of the analysis, without any of the files, for
+/// dashboards which poll for them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonHeadline
+{
+    /// Number of files parsed
+    pub files: u64,
+    /// Total lines of code
+    pub nloc: u64,
+    /// Mean complexity of the files, or zero if there are none
+    pub mean_cc: f64,
+    /// Median complexity of the files, or zero if there are none
+    pub median_cc: f64,
+    /// Highest complexity of the files, or zero if there are none
+    pub max_cc: f64,
+    /// The same totals for each language, keyed by its name
+    pub languages: BTreeMap<String, JsonLanguageSummary>,
+}
+
+/// Get the mean and highest complexity of some files, which are both zero
+/// if there aren't any
+fn mean_max(ccs: &[f64]) -> (f64, f64)
+{
+    match ccs.is_empty()
+    {
+        true => (0.0, 0.0),
+        false => (ccs.iter().sum::<f64>() / ccs.len() as f64, ccs.iter().cloned().fold(f64::MIN, f64::max)),
+    }
+}
+
+impl JsonHeadline
+{
+    pub fn new(files: &[FileResult]) -> JsonHeadline
+    {
+        let mut ccs: Vec<f64> = files.iter().map(|f| f.cc).collect();
+        ccs.sort_by(|a, b| a.total_cmp(b));
+
+        /* the median of an even number of files is the mean of the middle two */
+        let median_cc = match ccs.len()
+        {
+            0 => 0.0,
+            n if n % 2 == 0 => (ccs[n / 2 - 1] + ccs[n / 2]) / 2.0,
+            n => ccs[n / 2],
+        };
+
+        let mut by_language: BTreeMap<&str, Vec<&FileResult>> = BTreeMap::new();
+        for file in files
+        {
+            by_language.entry(&file.language).or_default().push(file);
+        }
+
+        let languages = by_language.into_iter()
+                                   .map(|(language, files)|
+                                   {
+                                       let (mean_cc, max_cc) = mean_max(&files.iter().map(|f| f.cc).collect::<Vec<f64>>());
+                                       let nloc = files.iter().map(|f| f.nloc).sum();
+
+                                       (language.to_string(), JsonLanguageSummary { files: files.len() as u64, nloc, mean_cc, max_cc })
+                                   })
+                                   .collect();
+
+        let (mean_cc, max_cc) = mean_max(&ccs);
+
+        JsonHeadline
+        {
+            files: files.len() as u64,
+            nloc: files.iter().map(|f| f.nloc).sum(),
+            mean_cc,
+            median_cc,
+            max_cc,
+            languages,
+        }
+    }
+}
+
+/// Serialize the headline numbers of the parsed files
+pub fn summary_json(files: &[FileResult], pretty: bool) -> String
+{
+    to_json(&JsonHeadline::new(files), pretty)
+}
+
+/// A directory in the ndjson summary
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonDirSummary
+{
+    /// Path to the directory from the root
+    pub dir: String,
+    /// Number of files below the directory, at any depth
+    pub files: u64,
+    /// Total lines of code
+    pub nloc: u64,
+    /// Mean complexity of the files
+    pub mean_cc: f64,
+    /// Highest complexity of the files
+    pub max_cc: f64,
+}
+
+/// Build the ndjson summary, one line for each directory in the treemap
+/// with the same totals the directory rollups are computed from
+pub fn ndjson_summary(treemap: &Treemap, root_label: Option<&str>) -> String
+{
+    let totals = treemap.dir_totals();
+
+    treemap.labels.iter()
+                  .zip(treemap.display_labels(root_label))
+                  .filter_map(|(label, dir)|
+                  {
+                      let total = totals.get(label)?;
+                      let summary = JsonDirSummary { dir, files: total.files, nloc: total.nloc, mean_cc: total.cc_mean(), max_cc: total.cc_max };
+
+                      Some(serde_json::to_string(&summary).unwrap())
+                  })
+                  .collect::<Vec<String>>()
+                  .join("\n")
+}
+
+/// Quote a string as a DOT id
+fn dot_quote(s: &str) -> String
+{
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Build a Graphviz DOT graph of the treemap hierarchy, with an edge from
+/// each node to its parent. The labels are used as the node ids, and the
+/// nodes are sized by their lines of code and colored by their complexity
+/// relative to the largest and most complex nodes
+pub fn dot(treemap: &Treemap, root_label: Option<&str>) -> String
+{
+    let max_nloc = treemap.nlocs.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let max_cc = treemap.ccs.iter().copied().fold(0.0, f64::max);
+
+    let mut graph = String::from("digraph cyclo {\n");
+    graph.push_str("    rankdir=LR;\n");
+    graph.push_str("    node [shape=box, style=filled, colorscheme=blues9];\n");
+
+    for (i, display) in treemap.display_labels(root_label).iter().enumerate()
+    {
+        /* only show the last component since the hierarchy is in the edges */
+        let name = display.rsplit('/').next().unwrap();
+
+        let width = 0.75 + 2.25 * (treemap.nlocs[i] as f64 / max_nloc).sqrt();
+        let color = match max_cc > 0.0
+        {
+            true => 1 + (8.0 * treemap.ccs[i] / max_cc).round() as u32,
+            false => 1,
+        };
+
+        graph.push_str(&format!("    {} [label={}, width={:.2}, fillcolor={}, tooltip=\"nloc: {}, cc: {:.2}\"];\n",
+                                dot_quote(&treemap.labels[i]), dot_quote(name), width, color, treemap.nlocs[i], treemap.ccs[i]));
+    }
+
+    for (label, parent) in treemap.labels.iter().zip(&treemap.parents)
+    {
+        if !parent.is_empty()
+        {
+            graph.push_str(&format!("    {} -> {};\n", dot_quote(parent), dot_quote(label)));
+        }
+    }
+
+    graph.push('}');
+
+    graph
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+use snafu::prelude::*;
+
+
+/// Errors cloning a remote repository
+#[derive(Debug, Snafu)]
+pub enum RemoteError
+{
+    #[snafu(display("Unable to create the directory '{}': {source}", dir.display()))]
+    CreateDir { dir: PathBuf, source: io::Error },
+
+    #[snafu(display("Unable to run git: {source}"))]
+    RunGit { source: io::Error },
+
+    #[snafu(display("Unable to clone {url}: {stderr}"))]
+    CloneFailed { url: String, stderr: String },
+}
+
+/// Check if a path is the URL of a git repository rather than a path on disk
+pub fn is_remote(path: &Path) -> bool
+{
+    let path = path.to_string_lossy();
+    let schemes = ["https://", "http://", "ssh://", "git://", "file://", "git@"];
+
+    schemes.iter()
+           .any(|n| path.starts_with(*n))
+}
+
+/// Get the name of the repository in a URL, like cyclo for
+/// https://github.com/user/cyclo.git, so the labels are rooted at it
+fn repo_name(url: &str) -> &str
+{
+    let name = url.trim_end_matches('/')
+                  .rsplit(['/', ':'])
+                  .next()
+                  .unwrap_or("")
+                  .trim_end_matches(".git");
+
+    match name.is_empty()
+    {
+        true => "repo",
+        false => name,
+    }
+}
+
+/// A shallow clone of a remote repository in a temporary directory, which is
+/// deleted when this is dropped
+#[derive(Debug)]
+pub struct RemoteClone
+{
+    /// The temporary directory holding the clone
+    dir: PathBuf,
+    /// The root of the clone, named after the repository
+    pub path: PathBuf,
+}
+
+impl RemoteClone
+{
+    /// Clone the latest commit of a repository. git is never allowed to
+    /// prompt for credentials, so a private repository fails rather than
+    /// hanging
+    pub fn new(url: &str) -> Result<RemoteClone, RemoteError>
+    {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        let dir = std::env::temp_dir().join(format!("cyclo-{}-{}", process::id(), nanos));
+        fs::create_dir(&dir).context(CreateDirSnafu { dir: &dir })?;
+
+        /* from here the directory is removed on any error */
+        let clone = RemoteClone { path: dir.join(repo_name(url)), dir };
+
+        let output = Command::new("git").args(["clone", "--quiet", "--depth", "1", "--", url])
+                                        .arg(&clone.path)
+                                        .env("GIT_TERMINAL_PROMPT", "0")
+                                        .output()
+                                        .context(RunGitSnafu)?;
+
+        ensure!(output.status.success(), CloneFailedSnafu { url, stderr: String::from_utf8_lossy(&output.stderr).trim().to_string() });
+
+        Ok(clone)
+    }
+}
+
+impl Drop for RemoteClone
+{
+    fn drop(&mut self)
+    {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,67 @@
+use std::env;
+use std::io::{self, IsTerminal};
+
+
+/// The colors text is painted in on the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color
+{
+    Red,
+    Yellow,
+    Green,
+    /// Faint, for the rows which are only context like directories
+    Dim,
+}
+
+impl Color
+{
+    /// The ANSI escape code selecting the color
+    fn code(self) -> &'static str
+    {
+        match self
+        {
+            Color::Red => "\x1b[31m",
+            Color::Yellow => "\x1b[33m",
+            Color::Green => "\x1b[32m",
+            Color::Dim => "\x1b[2m",
+        }
+    }
+}
+
+/// Check if the output to stderr should be colored, which it is only when
+/// stderr is a terminal and NO_COLOR isn't set (https://no-color.org)
+pub fn color_enabled() -> bool
+{
+    let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+
+    io::stderr().is_terminal() && !no_color
+}
+
+/// Get the color of a complexity relative to the mean complexity of the
+/// files. More than twice the mean is red, above the mean is yellow, and
+/// the rest is green
+pub fn complexity_color(cc: f64, mean: f64) -> Color
+{
+    if cc > 2.0 * mean
+    {
+        Color::Red
+    }
+    else if cc > mean
+    {
+        Color::Yellow
+    }
+    else
+    {
+        Color::Green
+    }
+}
+
+/// Paint text in a color, or leave it as it is if color isn't enabled
+pub fn paint(text: &str, color: Color, enabled: bool) -> String
+{
+    match enabled
+    {
+        true => format!("{}{}\x1b[0m", color.code(), text),
+        false => text.to_string(),
+    }
+}
@@ -0,0 +1,98 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+
+/// A phase of the analysis which is timed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase
+{
+    /// Walking the directory to find the files
+    Walk,
+    /// Counting the complexity of the files
+    Complexity,
+    /// Counting the lines of code of the files with tokei
+    Nloc,
+}
+
+/// Time spent in each phase of the analysis. The phases run on several
+/// threads at once, so the times are summed over all of them
+#[derive(Debug, Default)]
+pub struct Timings
+{
+    walk: AtomicU64,
+    complexity: AtomicU64,
+    nloc: AtomicU64,
+}
+
+impl Timings
+{
+    fn counter(&self, phase: Phase) -> &AtomicU64
+    {
+        match phase
+        {
+            Phase::Walk => &self.walk,
+            Phase::Complexity => &self.complexity,
+            Phase::Nloc => &self.nloc,
+        }
+    }
+
+    /// Add the time since `start` to a phase
+    pub fn record(&self, phase: Phase, start: Instant)
+    {
+        let nanos = start.elapsed().as_nanos().try_into().unwrap_or(u64::MAX);
+
+        self.counter(phase).fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    /// Get the total time spent in a phase
+    pub fn get(&self, phase: Phase) -> Duration
+    {
+        Duration::from_nanos(self.counter(phase).load(Ordering::Relaxed))
+    }
+}
+
+/// Statistics of the times taken by repeated runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunStats
+{
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub median: Duration,
+    /// Population standard deviation
+    pub stddev: Duration,
+}
+
+/// Get the statistics of the times taken by repeated runs, or None if there
+/// weren't any. The median of an even number of runs is the mean of the
+/// middle two
+pub fn run_stats(runs: &[Duration]) -> Option<RunStats>
+{
+    if runs.is_empty()
+    {
+        return None;
+    }
+
+    let mut sorted = runs.to_vec();
+    sorted.sort_unstable();
+
+    let secs: Vec<f64> = sorted.iter().map(|d| d.as_secs_f64()).collect();
+    let mean = secs.iter().sum::<f64>() / secs.len() as f64;
+    let variance = secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / secs.len() as f64;
+
+    let middle = sorted.len() / 2;
+    let median = match sorted.len() % 2
+    {
+        0 => (sorted[middle - 1] + sorted[middle]) / 2,
+        _ => sorted[middle],
+    };
+
+    Some(RunStats
+    {
+        min: sorted[0],
+        max: sorted[sorted.len() - 1],
+        mean: Duration::from_secs_f64(mean),
+        median,
+        stddev: Duration::from_secs_f64(variance.sqrt()),
+    })
+}
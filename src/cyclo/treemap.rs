@@ -0,0 +1,545 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use clap::ValueEnum;
+
+use crate::file_parser::{self, FileResult, ParserOptions};
+use crate::functions::Snippet;
+
+
+/// The key the treemap nodes are sorted by
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey
+{
+    /// Sort by label
+    Path,
+    /// Sort by complexity, highest first
+    Cc,
+    /// Sort by number of lines of code, highest first
+    Nloc,
+}
+
+/// How the complexities of the files in a directory are combined into the
+/// complexity of the directory
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirRollup
+{
+    /// Directories have no complexity
+    None,
+    /// Mean complexity of the files in the directory
+    Mean,
+    /// Highest complexity of the files in the directory
+    Max,
+    /// Total complexity of the files in the directory
+    Sum,
+}
+
+/// How the mean complexity used as the middle of the colorscale is computed
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeanMode
+{
+    /// Every node counts the same
+    Simple,
+    /// Each node is weighted by its lines of code, so a small file doesn't
+    /// count as much as a large one
+    Weighted,
+}
+
+/// How the files are grouped at the top of the treemap
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy
+{
+    /// Group the files by directory
+    Dir,
+    /// Group the files by language, then by directory
+    Language,
+}
+
+/// The combined values of the files below a directory
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DirTotals
+{
+    /// Number of files
+    pub files: u64,
+    /// Total lines of code
+    pub nloc: u64,
+    /// Total complexity
+    pub cc_sum: f64,
+    /// Highest complexity
+    pub cc_max: f64,
+}
+
+impl DirTotals
+{
+    /// Mean complexity of the files
+    pub fn cc_mean(&self) -> f64
+    {
+        self.cc_sum / self.files as f64
+    }
+
+    /// Combine the complexities of the files into the complexity of their
+    /// directory
+    pub fn cc(&self, rollup: DirRollup) -> f64
+    {
+        match rollup
+        {
+            DirRollup::None => 0.0,
+            DirRollup::Mean => self.cc_mean(),
+            DirRollup::Max => self.cc_max,
+            DirRollup::Sum => self.cc_sum,
+        }
+    }
+}
+
+/// Collapse the files deeper than a number of levels into a single node for
+/// their ancestor at that level, so a deep hierarchy is drawn flatter. The
+/// node has the total lines of code and functions of the files in it and
+/// their mean complexity, so every file is still counted. Its language is
+/// mixed if theirs differ
+pub fn collapse_files(files: &[FileResult], depth: usize) -> Vec<FileResult>
+{
+    let depth = depth.max(1);
+    let mut collapsed: Vec<FileResult> = Vec::new();
+    /* the index of each collapsed node and the number of files in it */
+    let mut nodes: HashMap<String, (usize, u64)> = HashMap::new();
+
+    for file in files
+    {
+        let components: Vec<&str> = file.label.split('/').collect();
+
+        if components.len() <= depth
+        {
+            collapsed.push(file.clone());
+            continue;
+        }
+
+        let label = components[..depth].join("/");
+
+        match nodes.get_mut(&label)
+        {
+            Some((index, count)) => {
+                let node = &mut collapsed[*index];
+                node.nloc += file.nloc;
+                node.cc += file.cc;
+                node.functions += file.functions;
+
+                if node.language != file.language
+                {
+                    node.language = "mixed".to_string();
+                }
+
+                *count += 1;
+            },
+            None => {
+                nodes.insert(label.clone(), (collapsed.len(), 1));
+
+                /* the node stands for the ancestor directory of the file */
+                let path = file.path.ancestors().nth(components.len() - depth).unwrap_or(&file.path).to_path_buf();
+                collapsed.push(FileResult { path, label, parent: components[..depth - 1].join("/"), ..file.clone() });
+            },
+        }
+    }
+
+    for (index, count) in nodes.values()
+    {
+        collapsed[*index].cc /= *count as f64;
+    }
+
+    collapsed
+}
+
+/// The data for the plotly Treemap. Each node is an element of the vectors,
+/// so they must always be the same length and in the same order
+#[derive(Debug, Default, Clone)]
+pub struct Treemap
+{
+    /// Number of lines of code for each node, which sizes the boxes
+    pub nlocs: Vec<u64>,
+    /// The path to each node from the root
+    pub labels: Vec<String>,
+    /// The label of the parent of each node, or empty for the root
+    pub parents: Vec<String>,
+    /// Complexity of each node, which colors the boxes
+    pub ccs: Vec<f64>,
+    /// The language of each file, shown when hovering over it. Empty for the
+    /// directories and functions
+    pub languages: Vec<String>,
+}
+
+impl Treemap
+{
+    /// Build the treemap from the parsed files, synthesizing a node for each
+    /// directory the files are in. Directories have no lines of code or
+    /// complexity of their own
+    pub fn from_files(files: &[FileResult]) -> Treemap
+    {
+        let mut treemap = Treemap::default();
+
+        for file in files
+        {
+            treemap.insert_file(file);
+        }
+
+        treemap.check();
+
+        treemap
+    }
+
+    /// Add a file and the directories above it which aren't in the treemap
+    /// yet
+    fn insert_file(&mut self, file: &FileResult)
+    {
+        self.push_file(file);
+
+        /* split the label to get the parent dirs, and walk up until a dir
+         * that has already been added is hit since its parents will be too */
+        let mut dirs = file.label.split('/').collect::<Vec<&str>>();
+
+        /* pop to remove filename from path */
+        dirs.pop();
+
+        while !dirs.is_empty()
+        {
+            let dir = dirs.join("/");
+
+            if self.labels.contains(&dir)
+            {
+                break;
+            }
+
+            dirs.pop();
+
+            self.push(dir, dirs.join("/"), 0, 0.0);
+        }
+    }
+
+    /// Update a file which was parsed again in place, or add it if it's new,
+    /// then the rollups of the directories above it. The rest of the nodes
+    /// are left as they are, so a single change doesn't rebuild the treemap
+    pub fn update_file(&mut self, file: &FileResult, rollup: DirRollup)
+    {
+        match self.labels.iter().position(|l| *l == file.label)
+        {
+            Some(i) => {
+                self.nlocs[i] = file.nloc;
+                self.ccs[i] = file.cc;
+                self.languages[i] = file.language.clone();
+            },
+            None => self.insert_file(file),
+        }
+
+        self.rollup_ancestors(&file.label, rollup);
+        self.check();
+    }
+
+    /// Remove a file which no longer exists, along with any directories
+    /// above it which are left empty, then update the rollups of the rest
+    pub fn remove_file(&mut self, label: &str, rollup: DirRollup)
+    {
+        let mut label = label.to_string();
+
+        while let Some(i) = self.labels.iter().position(|l| *l == label)
+        {
+            if self.parents.contains(&label)
+            {
+                break;
+            }
+
+            self.nlocs.remove(i);
+            self.labels.remove(i);
+            self.ccs.remove(i);
+            self.languages.remove(i);
+            label = self.parents.remove(i);
+        }
+
+        self.rollup_ancestors(&format!("{}/", label), rollup);
+        self.check();
+    }
+
+    /// Set the rollups of the directories above a node from the files below
+    /// each of them, like a full rollup does for every directory
+    fn rollup_ancestors(&mut self, label: &str, rollup: DirRollup)
+    {
+        if rollup == DirRollup::None
+        {
+            return;
+        }
+
+        let dirs: HashSet<&String> = self.parents.iter().collect();
+        let mut ancestors: HashMap<&str, DirTotals> = HashMap::new();
+        let mut path = label;
+
+        while let Some((dir, _)) = path.rsplit_once('/')
+        {
+            ancestors.insert(dir, DirTotals::default());
+            path = dir;
+        }
+
+        for ((node, cc), nloc) in self.labels.iter().zip(&self.ccs).zip(&self.nlocs)
+        {
+            if dirs.contains(node)
+            {
+                continue;
+            }
+
+            for (dir, total) in ancestors.iter_mut()
+            {
+                if node.strip_prefix(*dir).is_some_and(|rest| rest.starts_with('/'))
+                {
+                    total.files += 1;
+                    total.nloc += nloc;
+                    total.cc_sum += cc;
+                    total.cc_max = total.cc_max.max(*cc);
+                }
+            }
+        }
+
+        let ccs: Vec<(usize, f64)> = self.labels.iter()
+                                                .enumerate()
+                                                .filter_map(|(i, node)| ancestors.get(node.as_str()).map(|total| (i, total.cc(rollup))))
+                                                .collect();
+
+        for (i, cc) in ccs
+        {
+            self.ccs[i] = cc;
+        }
+    }
+
+    /// Build the treemap from the parsed files grouped by a key. Grouping by
+    /// language adds a node for each language above the directories, which
+    /// are split between the languages of the files in them
+    pub fn from_files_grouped(files: &[FileResult], group_by: GroupBy) -> Treemap
+    {
+        if group_by == GroupBy::Dir
+        {
+            return Treemap::from_files(files);
+        }
+
+        let files: Vec<FileResult> = files.iter()
+                                          .map(|f|
+                                          {
+                                              let language = &f.language;
+                                              let parent = match f.parent.is_empty()
+                                              {
+                                                  true => language.to_string(),
+                                                  false => format!("{}/{}", language, f.parent),
+                                              };
+
+                                              FileResult { label: format!("{}/{}", language, f.label), parent, ..f.clone() }
+                                          })
+                                          .collect();
+
+        Treemap::from_files(&files)
+    }
+
+    /// Add a node under each file for each of its functions, sized by its
+    /// lines of code and colored by its complexity. A file's own size is
+    /// what's left of its lines of code outside its functions, since plotly
+    /// adds the children to it. Functions of files which aren't in the
+    /// treemap are left out
+    pub fn add_functions(&mut self, functions: &[Snippet], options: &ParserOptions)
+    {
+        let index: HashMap<String, usize> = self.labels.iter()
+                                                       .enumerate()
+                                                       .map(|(i, label)| (label.clone(), i))
+                                                       .collect();
+
+        for function in functions
+        {
+            /* grouping by language puts the files under their language */
+            let grouped = format!("{}/{}", file_parser::language_name(&function.label), function.label);
+            let file = match index.get(&function.label).or_else(|| index.get(&grouped))
+            {
+                Some(file) => *file,
+                None => continue,
+            };
+
+            /* the blank and comment lines of the function aren't in the file's
+             * lines of code either */
+            let lines = file_parser::source_nloc(&function.label, &function.source, options).unwrap_or((function.end - function.start + 1) as u64);
+            self.nlocs[file] = self.nlocs[file].saturating_sub(lines);
+
+            let label = format!("{}:{}", self.labels[file], function.start);
+            let parent = self.labels[file].clone();
+            self.push(label, parent, lines, function.cc);
+        }
+    }
+
+    /// Add a node which isn't a file to the treemap
+    pub fn push(&mut self, label: String, parent: String, nloc: u64, cc: f64)
+    {
+        self.labels.push(label);
+        self.parents.push(parent);
+        self.nlocs.push(nloc);
+        self.ccs.push(cc);
+        self.languages.push(String::new());
+    }
+
+    /// Add a file to the treemap, with its language
+    pub fn push_file(&mut self, file: &FileResult)
+    {
+        self.push(file.label.clone(), file.parent.clone(), file.nloc, file.cc);
+        *self.languages.last_mut().unwrap() = file.language.clone();
+    }
+
+    /// Number of nodes in the treemap
+    pub fn len(&self) -> usize
+    {
+        self.labels.len()
+    }
+
+    /// Whether the treemap has no nodes
+    pub fn is_empty(&self) -> bool
+    {
+        self.labels.is_empty()
+    }
+
+    /// Get the mean complexity of the nodes, or zero if there are none.
+    /// Directories have no lines of code so aren't counted by the weighted
+    /// mean
+    pub fn mean(&self, mode: MeanMode) -> f64
+    {
+        let (sum, count) = match mode
+        {
+            MeanMode::Simple => (self.ccs.iter().sum::<f64>(), self.len() as f64),
+            MeanMode::Weighted => (self.ccs.iter().zip(&self.nlocs).map(|(cc, nloc)| cc * *nloc as f64).sum::<f64>(),
+                                   self.nlocs.iter().sum::<u64>() as f64),
+        };
+
+        if count == 0.0
+        {
+            return 0.0;
+        }
+
+        sum / count
+    }
+
+    /// Test lengths of the vecs, since they must all be the same
+    pub fn check(&self)
+    {
+        assert_eq!(self.nlocs.len(), self.labels.len(), "nloc ({}) and label ({}) vector length equality failed", self.nlocs.len(), self.labels.len());
+        assert_eq!(self.labels.len(), self.parents.len(), "labels ({}) and parents ({}) vector length equality failed", self.labels.len(), self.parents.len());
+        assert_eq!(self.parents.len(), self.ccs.len(), "parents ({}) and ccs ({}) vector lengthe equality failed", self.parents.len(), self.ccs.len());
+        assert_eq!(self.ccs.len(), self.languages.len(), "ccs ({}) and languages ({}) vector length equality failed", self.ccs.len(), self.languages.len());
+    }
+
+    /// Get the labels to display for each node, with the root node renamed to
+    /// the given label. The nodes below the root are renamed to match, since
+    /// their labels start with the root's
+    pub fn display_labels(&self, root_label: Option<&str>) -> Vec<String>
+    {
+        let root_label = match root_label
+        {
+            Some(root_label) => root_label,
+            None => return self.labels.clone(),
+        };
+
+        let roots: Vec<&String> = self.labels.iter()
+                                             .zip(&self.parents)
+                                             .filter(|(_, parent)| parent.is_empty())
+                                             .map(|(label, _)| label)
+                                             .collect();
+
+        self.labels.iter()
+                   .map(|label|
+                   {
+                       for root in &roots
+                       {
+                           if label == *root
+                           {
+                               return root_label.to_string();
+                           }
+
+                           if let Some(rest) = label.strip_prefix(root.as_str()).and_then(|r| r.strip_prefix('/'))
+                           {
+                               return format!("{}/{}", root_label, rest);
+                           }
+                       }
+
+                       label.clone()
+                   })
+                   .collect()
+    }
+
+    /// Get the totals of the files below each directory, at any depth. Only
+    /// the files are combined, so any rollups already set on subdirectories
+    /// aren't counted twice
+    pub fn dir_totals(&self) -> HashMap<String, DirTotals>
+    {
+        let dirs: HashSet<&String> = self.parents.iter().collect();
+        let mut totals: HashMap<String, DirTotals> = HashMap::new();
+
+        for ((label, cc), nloc) in self.labels.iter().zip(&self.ccs).zip(&self.nlocs)
+        {
+            if dirs.contains(label)
+            {
+                continue;
+            }
+
+            let mut path = label.as_str();
+
+            while let Some((dir, _)) = path.rsplit_once('/')
+            {
+                let total = totals.entry(dir.to_string()).or_default();
+                total.files += 1;
+                total.nloc += nloc;
+                total.cc_sum += cc;
+                total.cc_max = total.cc_max.max(*cc);
+
+                path = dir;
+            }
+        }
+
+        totals
+    }
+
+    /// Set the complexity of each directory from the complexities of all the
+    /// files below it
+    pub fn rollup(&mut self, rollup: DirRollup)
+    {
+        if rollup == DirRollup::None
+        {
+            return;
+        }
+
+        let totals = self.dir_totals();
+
+        for (label, cc) in self.labels.iter().zip(self.ccs.iter_mut())
+        {
+            if let Some(total) = totals.get(label)
+            {
+                *cc = total.cc(rollup);
+            }
+        }
+    }
+
+    /// Sort the nodes by a key, keeping the vectors in lockstep. Ties are
+    /// broken by label so the order is always the same
+    pub fn sort(&mut self, key: SortKey)
+    {
+        let mut order: Vec<usize> = (0..self.len()).collect();
+
+        order.sort_by(|&a, &b|
+        {
+            let ordering = match key
+            {
+                SortKey::Path => Ordering::Equal,
+                SortKey::Cc => self.ccs[b].total_cmp(&self.ccs[a]),
+                SortKey::Nloc => self.nlocs[b].cmp(&self.nlocs[a]),
+            };
+
+            ordering.then_with(|| self.labels[a].cmp(&self.labels[b]))
+        });
+
+        self.reorder(&order);
+    }
+
+    /// Rebuild the vectors from the node indices in the given order
+    fn reorder(&mut self, order: &[usize])
+    {
+        self.nlocs = order.iter().map(|&i| self.nlocs[i]).collect();
+        self.labels = order.iter().map(|&i| self.labels[i].clone()).collect();
+        self.parents = order.iter().map(|&i| self.parents[i].clone()).collect();
+        self.ccs = order.iter().map(|&i| self.ccs[i]).collect();
+        self.languages = order.iter().map(|&i| self.languages[i].clone()).collect();
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::HashSet;
+use serde_json::Value;
+use snafu::prelude::*;
+
+use crate::output::JsonReport;
+
+
+/// The arrays of the treemap data, which must all have a value for each node
+const TREEMAP_ARRAYS: [&str; 6] = ["values", "ids", "labels", "parents", "hovertext", "colors"];
+
+/// Ways the generated output can be broken
+#[derive(Debug, Snafu)]
+pub enum ValidateError
+{
+    #[snafu(display("The treemap data has no {name} array"))]
+    MissingArray { name: String },
+
+    #[snafu(display("The treemap {name} array isn't valid JSON: {source}"))]
+    InvalidArray { name: String, source: serde_json::Error },
+
+    #[snafu(display("The treemap {name} array has {len} values, but there are {nodes} nodes"))]
+    LengthMismatch { name: String, len: usize, nodes: usize },
+
+    #[snafu(display("The treemap has the id '{id}' more than once"))]
+    DuplicateId { id: String },
+
+    #[snafu(display("The parent '{parent}' of '{id}' isn't in the treemap"))]
+    MissingParent { id: String, parent: String },
+
+    #[snafu(display("The treemap has no root node"))]
+    NoRoot,
+
+    #[snafu(display("The snippets aren't valid JSON: {source}"))]
+    InvalidSnippets { source: serde_json::Error },
+
+    #[snafu(display("The JSON report isn't valid: {source}"))]
+    InvalidReport { source: serde_json::Error },
+}
+
+/// Find a JavaScript property holding a JSON value in the generated
+/// treemap data, like `values: [1, 2]`, and parse it
+fn property(js: &str, name: &str) -> Result<Value, ValidateError>
+{
+    let key = format!("{}: ", name);
+
+    /* the colors are inside the marker object, the rest are a line each */
+    let start = js.find(&format!("\n        {}", key))
+                  .or_else(|| js.find(&format!("{{{}", key)))
+                  .context(MissingArraySnafu { name })?;
+    let value = &js[start..];
+    let value = &value[value.find(&key).unwrap() + key.len()..];
+
+    /* the array ends at the bracket matching its opening one, skipping any
+     * brackets in strings */
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut end = value.len();
+
+    for (i, c) in value.char_indices()
+    {
+        match c
+        {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => {
+                depth -= 1;
+
+                if depth == 0
+                {
+                    end = i + 1;
+                    break;
+                }
+            },
+            _ => {},
+        }
+    }
+
+    serde_json::from_str(&value[..end]).context(InvalidArraySnafu { name })
+}
+
+/// Check the treemap data read by the dashboard is well formed: every array
+/// is valid JSON with a value for each node, the ids are unique, and every
+/// parent is one of the ids. A broken file only shows as a blank page
+/// otherwise
+pub fn validate_js(js: &str) -> Result<(), ValidateError>
+{
+    let mut arrays = Vec::new();
+
+    for name in TREEMAP_ARRAYS
+    {
+        match property(js, name)?
+        {
+            Value::Array(array) => arrays.push((name, array)),
+            _ => return MissingArraySnafu { name }.fail(),
+        }
+    }
+
+    let nodes = arrays[0].1.len();
+
+    for (name, array) in &arrays
+    {
+        ensure!(array.len() == nodes, LengthMismatchSnafu { name: *name, len: array.len(), nodes });
+    }
+
+    let as_str = |value: &Value| value.as_str().unwrap_or_default().to_string();
+    let ids: Vec<String> = arrays[1].1.iter().map(as_str).collect();
+    let parents: Vec<String> = arrays[3].1.iter().map(as_str).collect();
+
+    let mut seen = HashSet::new();
+    for id in &ids
+    {
+        ensure!(seen.insert(id.as_str()), DuplicateIdSnafu { id });
+    }
+
+    for (id, parent) in ids.iter().zip(&parents)
+    {
+        ensure!(parent.is_empty() || seen.contains(parent.as_str()), MissingParentSnafu { id, parent });
+    }
+
+    ensure!(nodes == 0 || parents.iter().any(|p| p.is_empty()), NoRootSnafu);
+
+    /* the snippets are a global, or exported by a module */
+    for key in ["var snippets = ", "export const snippets = "]
+    {
+        if let Some(start) = js.find(key)
+        {
+            let snippets = js[start + key.len()..].lines().next().unwrap_or_default();
+            serde_json::from_str::<Value>(snippets).context(InvalidSnippetsSnafu)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the JSON report can be read back
+pub fn validate_json(json: &str) -> Result<(), ValidateError>
+{
+    serde_json::from_str::<JsonReport>(json).context(InvalidReportSnafu)?;
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::file_parser::{FileResult, ParserOptions};
+use crate::treemap::{DirRollup, Treemap};
+
+
+/// The files which changed between two snapshots of a directory
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Changes
+{
+    /// Files which are new or were modified, in path order
+    pub changed: Vec<PathBuf>,
+    /// Files which no longer exist, in path order
+    pub removed: Vec<PathBuf>,
+}
+
+impl Changes
+{
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool
+    {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// How long after a directory was modified it's still walked again, since
+/// the modification times are only as precise as the filesystem's clock and
+/// a file added just after a snapshot can leave the time the same
+const RECENT: Duration = Duration::from_secs(2);
+
+/// The files which would be analyzed and the directories they're in, with
+/// their modification times. A directory's time changes when a file is
+/// added to or removed from it, so only those directories are walked again
+#[derive(Debug, Clone)]
+pub struct Snapshot
+{
+    /// The modification time of each file
+    pub files: HashMap<PathBuf, SystemTime>,
+    /// The modification time of each directory
+    dirs: HashMap<PathBuf, SystemTime>,
+    /// When the snapshot was taken
+    taken: SystemTime,
+}
+
+impl Snapshot
+{
+    /// Walk the whole tree for the first snapshot. The files are only looked
+    /// at, not read
+    pub fn new(path: &Path, options: &ParserOptions) -> Snapshot
+    {
+        let mut snapshot = Snapshot { files: HashMap::new(), dirs: HashMap::new(), taken: SystemTime::now() };
+        snapshot.add(path, path, usize::MAX, options);
+
+        snapshot
+    }
+
+    /// Add the entries under a directory of the tree, up to a depth below it
+    fn add(&mut self, root: &Path, dir: &Path, max_depth: usize, options: &ParserOptions)
+    {
+        for entry in crate::walk_watched(root, dir, max_depth, options)
+        {
+            let modified = match entry.metadata().ok().and_then(|m| m.modified().ok())
+            {
+                Some(modified) => modified,
+                None => continue,
+            };
+
+            match entry.file_type().is_dir()
+            {
+                true => self.dirs.insert(entry.into_path(), modified),
+                false => self.files.insert(entry.into_path(), modified),
+            };
+        }
+    }
+
+    /// Take the next snapshot by looking at the files and directories which
+    /// are already known, only walking the directories which changed to find
+    /// the new files. A new directory is walked in full
+    pub fn refresh(&self, path: &Path, options: &ParserOptions) -> Snapshot
+    {
+        let modified = |p: &PathBuf| Some((p.clone(), fs::metadata(p).ok()?.modified().ok()?));
+        let mut next = Snapshot
+        {
+            files: self.files.keys().filter_map(modified).collect(),
+            dirs: self.dirs.keys().filter_map(modified).collect(),
+            taken: SystemTime::now(),
+        };
+
+        let recent = self.taken.checked_sub(RECENT).unwrap_or(SystemTime::UNIX_EPOCH);
+        let changed: Vec<PathBuf> = next.dirs.iter()
+                                             .filter(|(dir, time)| self.dirs.get(*dir) != Some(*time) || **time >= recent)
+                                             .map(|(dir, _)| dir.clone())
+                                             .collect();
+
+        for dir in changed
+        {
+            let known: HashSet<PathBuf> = next.dirs.keys().cloned().collect();
+            next.add(path, &dir, 1, options);
+
+            /* the directories which weren't there before are new */
+            let new: Vec<PathBuf> = next.dirs.keys()
+                                             .filter(|d| !known.contains(*d))
+                                             .cloned()
+                                             .collect();
+
+            for dir in new
+            {
+                next.add(path, &dir, usize::MAX, options);
+            }
+        }
+
+        next
+    }
+}
+
+/// Compare two snapshots of the modification times, finding the files which
+/// have to be parsed again and the ones to drop
+pub fn changes(before: &HashMap<PathBuf, SystemTime>, after: &HashMap<PathBuf, SystemTime>) -> Changes
+{
+    let mut changed: Vec<PathBuf> = after.iter()
+                                         .filter(|(path, modified)| before.get(*path) != Some(*modified))
+                                         .map(|(path, _)| path.clone())
+                                         .collect();
+    let mut removed: Vec<PathBuf> = before.keys()
+                                          .filter(|path| !after.contains_key(*path))
+                                          .cloned()
+                                          .collect();
+
+    changed.sort();
+    removed.sort();
+
+    Changes { changed, removed }
+}
+
+/// Put a file which was parsed again into the results and the treemap. The
+/// results stay in label order, like a full run. A file which has fewer than
+/// `min_functions` functions now is dropped like a removed one
+pub fn update(files: &mut Vec<FileResult>, treemap: &mut Treemap, file: FileResult, min_functions: Option<u64>, rollup: DirRollup)
+{
+    if !file.has_functions(min_functions)
+    {
+        if let Some(i) = files.iter().position(|f| f.label == file.label)
+        {
+            treemap.remove_file(&files.remove(i).label, rollup);
+        }
+
+        return;
+    }
+
+    treemap.update_file(&file, rollup);
+
+    match files.binary_search_by(|f| f.label.cmp(&file.label))
+    {
+        Ok(i) => files[i] = file,
+        Err(i) => files.insert(i, file),
+    }
+}
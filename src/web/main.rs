@@ -1,17 +1,60 @@
-use std::net::{TcpListener, TcpStream, SocketAddr};
-use std::path::Path;
-use std::io::{Read, Write};
-use std::fs;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::net::{Shutdown, TcpListener, TcpStream, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::io::{self, Read, Write};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use std::fs::{self, File};
 use clap::Parser;
 
 
+/// The dashboard served at /, relative to the directory the server is run in
+const INDEX: &str = "./html/index.html";
+
+/// Served at / in place of the dashboard when it is missing, since a bare 404
+/// doesn't say what went wrong
+const MISSING_DASHBOARD: &str = "<!DOCTYPE html>
+<html>
+<head><title>cyclo</title></head>
+<body>
+<h1>No dashboard to show yet</h1>
+<p>The webserver serves the dashboard in <code>html/</code> under the directory it is run in, which has no <code>index.html</code>.</p>
+<p>Run <code>cyclo -p &lt;path&gt;</code> first to generate the dashboard, then start the webserver from the same directory.</p>
+</body>
+</html>
+";
+
 #[derive(Parser,Debug)]
 #[clap(name="webserver")]
 struct Args
 {
-    /// webserver port
+    /// webserver port. A free port is used instead if it is taken, and the
+    /// address listened on is printed
     #[clap(short = 'p', long, value_parser)]
     port: u16,
+    /// JSON analysis results written by cyclo --format json, served at
+    /// /api/complexity
+    #[clap(short = 'd', long, value_parser)]
+    data: Option<PathBuf>,
+    /// Number of threads handling connections
+    #[clap(short = 'j', long, value_parser, default_value_t = default_threads())]
+    threads: usize,
+    /// Seconds a client has to send its request, or to accept the response,
+    /// before the connection is dropped
+    #[clap(short = 't', long, value_parser, default_value_t = 10)]
+    timeout: u64,
+    /// Largest file in bytes the server will send. A larger file, like a
+    /// data dump left in html/, is refused with 413 Payload Too Large
+    #[clap(long, value_parser, default_value_t = 100 * 1024 * 1024)]
+    max_body_size: u64,
+}
+
+/// Default to as many threads as there is available parallelism
+fn default_threads() -> usize
+{
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
 }
 
 /// Struct representing the barebones for a generic HTTP request
@@ -19,59 +62,199 @@ struct Args
 struct HttpRequest
 {
     method: String,
-    uri: String
+    uri: String,
+    /// The ETag from the If-None-Match header, if the client has a cached copy
+    if_none_match: Option<String>,
 }
 
 impl HttpRequest
 {
-    fn new(request_data: String) -> Self
+    /// Parse the request line, accepting either \r\n or bare \n line
+    /// endings. Returns None if the request line is missing the method or URI,
+    /// e.g. if the client closed the connection without sending anything
+    fn new(request_data: &str) -> Option<Self>
     {
-        let req: Vec<&str> = request_data.splitn(2, "\r\n").collect();
-        /* status line is GET / HTTP/1.1 etc */
-        let status_line = req[0];
+        /* status line is GET / HTTP/1.1 etc, and lines() strips a trailing \r */
+        let status_line = request_data.lines().next()?;
 
+        let mut stat = status_line.split_whitespace();
         /* this grabs the method like GET */
-        let stat: Vec<&str> = status_line.split(" ").collect();
-        let method = stat[0].to_string();
+        let method = stat.next()?.to_string();
         /* this grabs the URI, like / */
-        /* TODO: sometimes this panics as stat.len() is 1 so stat[1] is out-of-bounds.
-         * not sure why this is happening? */
-        let uri = stat[1].to_string();
+        let uri = stat.next()?.to_string();
+
+        /* the headers end at the first empty line, header names are case
+         * insensitive */
+        let if_none_match = request_data.lines()
+                                        .skip(1)
+                                        .take_while(|line| !line.is_empty())
+                                        .filter_map(|line| line.split_once(':'))
+                                        .find(|(name, _)| name.trim().eq_ignore_ascii_case("If-None-Match"))
+                                        .map(|(_, value)| value.trim().to_string());
+
+        Some(HttpRequest { method, uri, if_none_match })
+    }
+}
+
+/// Get the ETag for a file, which is a hash of its size and modification
+/// time so the file doesn't have to be read to check it
+fn etag(metadata: &fs::Metadata) -> String
+{
+    let mut hasher = DefaultHasher::new();
+    metadata.len().hash(&mut hasher);
+    metadata.modified().ok().hash(&mut hasher);
+
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Build the status line and headers of a response. Each line ends with
+/// \r\n and the block always ends with the blank line separating it from
+/// the body, whatever the headers are
+fn head(status: &str, headers: &[(&str, &str)]) -> String
+{
+    let mut head = format!("HTTP/1.1 {}\r\n", status);
+
+    for (name, value) in headers
+    {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    head.push_str("\r\n");
+
+    head
+}
+
+/// Respond with a body held in memory. The Content-Length is the number of
+/// bytes in the body, not characters
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> io::Result<()>
+{
+    let content_length = body.len().to_string();
+    let head = head(status, &[("Content-Length", &content_length), ("Content-Type", content_type), ("Cache-Control", "no-cache")]);
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)
+}
+
+/// Respond that there is nothing at the path
+fn not_found(stream: &mut TcpStream) -> io::Result<()>
+{
+    respond(stream, "404 Not Found", "text/plain", b"Not Found")
+}
+
+/// Respond with a file. The client has to revalidate its cached copy each
+/// time since the analysis can be rerun at any point, but if its ETag still
+/// matches the file isn't sent again. The body is streamed to the socket in
+/// chunks after the headers rather than read into memory, since the
+/// generated JS can be huge. A file larger than the maximum body size isn't
+/// sent at all
+fn file_response(stream: &mut TcpStream, path: &Path, content_type: &str, request: &HttpRequest, max_body_size: u64) -> io::Result<()>
+{
+    let mut file = match File::open(path)
+    {
+        Ok(file) => file,
+        Err(_) => return not_found(stream),
+    };
+
+    let metadata = file.metadata()?;
+
+    if metadata.len() > max_body_size
+    {
+        return respond(stream, "413 Payload Too Large", "text/plain", b"Payload Too Large");
+    }
+
+    let etag = etag(&metadata);
+
+    if request.if_none_match.as_deref() == Some(etag.as_str())
+    {
+        return stream.write_all(head("304 Not Modified", &[("Cache-Control", "no-cache"), ("ETag", &etag)]).as_bytes());
+    }
 
-        HttpRequest { method, uri }
+    /* the length of the file is in bytes, so it matches the body whatever
+     * its encoding */
+    let content_length = metadata.len().to_string();
+    let head = head("200 OK", &[("Content-Length", &content_length), ("Content-Type", content_type), ("Cache-Control", "no-cache"), ("ETag", &etag)]);
+
+    stream.write_all(head.as_bytes())?;
+
+    /* cyclo may rewrite the file while it's sent, so no more than the length
+     * in the header is sent, and if it shrank the connection is closed so
+     * the client can tell the body was cut short */
+    let sent = io::copy(&mut (&mut file).take(metadata.len()), stream)?;
+
+    if sent < metadata.len()
+    {
+        stream.shutdown(Shutdown::Both)?;
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the file shrank while it was sent"));
     }
+
+    Ok(())
 }
 
-/// Handle the HTTP request
-fn handle_connection(mut stream: TcpStream)
+/// Handle the HTTP request. A slow or idle client is dropped once the
+/// timeout passes, rather than holding up the worker
+fn handle_connection(mut stream: TcpStream, data: Option<&Path>, timeout: Duration, max_body_size: u64)
 {
     let mut buf = vec![0;2048];
 
-    stream.read(&mut buf).unwrap();
+    if stream.set_read_timeout(Some(timeout)).and_then(|_| stream.set_write_timeout(Some(timeout))).is_err()
+    {
+        return;
+    }
+
+    /* the client may have gone away or timed out, which shouldn't take down
+     * the worker */
+    let len = match stream.read(&mut buf)
+    {
+        Ok(len) => len,
+        Err(_) => return,
+    };
 
-    let request_data = String::from_utf8_lossy(&buf);
-    let request = HttpRequest::new(request_data.to_string());
+    let request_data = String::from_utf8_lossy(&buf[..len]);
+    let request = match HttpRequest::new(&request_data)
+    {
+        Some(request) => request,
+        None => {
+            let _ = respond(&mut stream, "400 Bad Request", "text/plain", b"Bad Request");
+            return;
+        }
+    };
 
-    let response = if request.method == "GET"
+    let result = if request.method == "GET" && request.uri == "/healthz"
+    {
+        /* liveness probe, which doesn't depend on any files existing */
+        respond(&mut stream, "200 OK", "text/plain", b"ok")
+    }
+    else if request.method == "GET" && request.uri == "/api/complexity"
+    {
+        /* respond to GET /api/complexity with the JSON analysis results */
+        match data
+        {
+            Some(data) => file_response(&mut stream, data, "application/json", &request, max_body_size),
+            None => not_found(&mut stream),
+        }
+    }
+    else if request.method == "GET"
     {
 
         // parse the URI so if the user navigates to it, it'll just hit a 404
-        let filename: String = if request.uri == "/"
+        let filename = if request.uri == "/"
         {
-            "index.html".to_string()
+            "index.html"
         }
         else
         {
-            request.uri
+            &request.uri
         };
 
         let path = format!("./html/{}", filename);
 
-        if Path::new(&path).exists()
+        if request.uri == "/" && !Path::new(INDEX).is_file()
         {
-            let content = fs::read_to_string(&path).unwrap();
-
-            let mime_type = Path::new(&path).extension().unwrap().to_string_lossy();
+            respond(&mut stream, "404 Not Found", "text/html", MISSING_DASHBOARD.as_bytes())
+        }
+        else if Path::new(&path).is_file()
+        {
+            let mime_type = Path::new(&path).extension().unwrap_or_default().to_string_lossy();
             let mime_type = if mime_type == "js"
             {
                 "javascript".to_string()
@@ -84,37 +267,40 @@ fn handle_connection(mut stream: TcpStream)
             let content_type = format!("text/{}", mime_type);
 
             // response
-            format!(
-                "HTTP/1.1 200 OK\r\nContent-Length: {content_length}\r\nContent-Type: {content_type}\r\n\r\n{body}",
-                content_length=content.len(),
-                content_type=content_type,
-                body=content)
+            file_response(&mut stream, Path::new(&path), &content_type, &request, max_body_size)
         }
         else
         {
-            "HTTP/1.1 404 Not Found\r\n\r\nNot Found".to_string()
+            not_found(&mut stream)
         }
     }
     else
     {
-        "HTTP/1.1 501 Not Implemented\r\n\r\nNot Implemented".to_string()
+        respond(&mut stream, "501 Not Implemented", "text/plain", b"Not Implemented")
     };
 
-    stream.write(response.as_bytes()).unwrap();
-    stream.flush().unwrap();
+    let _ = result.and_then(|_| stream.flush());
+}
+
+/// Listen on the port, or on one the OS picks if it's taken, since any fixed
+/// fallback could be taken too when several servers run on one host
+fn bind(port: u16) -> io::Result<TcpListener>
+{
+    match TcpListener::bind(SocketAddr::from(([127,0,0,1], port)))
+    {
+        Err(error) if error.kind() == io::ErrorKind::AddrInUse && port != 0 => {
+            eprintln!("Warning: port {} is in use, using a free port instead", port);
+            TcpListener::bind(SocketAddr::from(([127,0,0,1], 0)))
+        },
+        result => result,
+    }
 }
 
 fn main()
 {
     let args = Args::parse();
 
-    // if args.port fails, bind to 3030
-    let addrs = [
-        SocketAddr::from(([127,0,0,1], args.port)),
-        SocketAddr::from(([127,0,0,1], 3030)),
-    ];
-
-    let listener = match TcpListener::bind(&addrs[..])
+    let listener = match bind(args.port)
     {
         Ok(listen) => listen,
         Err(error) => panic!("failed to start TcpListener: {:?}", error),
@@ -124,10 +310,47 @@ fn main()
     // randomly assign a port
     println!("starting webserver at {:?}", listener.local_addr().unwrap());
 
+    /* the server still starts, since the API and health check don't need
+     * the dashboard */
+    if !Path::new(INDEX).is_file()
+    {
+        eprintln!("Warning: there is no dashboard at {}. Run `cyclo -p <path>` first to generate the dashboard, then start the webserver from the same directory", INDEX);
+    }
+
+    // the connections are handed to a fixed pool of workers, so a slow client
+    // doesn't hold up the others
+    let (stream_tx, stream_rx) = mpsc::channel::<TcpStream>();
+    let stream_rx = Arc::new(Mutex::new(stream_rx));
+    let data = Arc::new(args.data);
+    /* a zero timeout isn't allowed, so it's at least a second */
+    let timeout = Duration::from_secs(args.timeout.max(1));
+    let max_body_size = args.max_body_size;
+
+    for _ in 0..args.threads.max(1)
+    {
+        let stream_rx = Arc::clone(&stream_rx);
+        let data = Arc::clone(&data);
+
+        thread::spawn(move ||
+        {
+            loop
+            {
+                /* the lock is only held while receiving, not handling */
+                let stream = match stream_rx.lock().unwrap().recv()
+                {
+                    Ok(stream) => stream,
+                    Err(_) => break,
+                };
+
+                handle_connection(stream, data.as_deref(), timeout, max_body_size);
+            }
+        });
+    }
+
     for stream in listener.incoming()
     {
         let stream = stream.unwrap();
 
-        handle_connection(stream);
+        stream_tx.send(stream).unwrap();
     }
 }
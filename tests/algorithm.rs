@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use cyclo::algorithm::Algorithm;
+use cyclo::file_parser::{self, ParserOptions};
+use cyclo::halstead;
+
+
+/// A function with an if nested in a while, then a function with an if and
+/// an elif at its top level
+const NESTED: &str = "def f(items):
+    while items:
+        if items[0] and ready:
+            print(items.pop())
+
+def g(x):
+    if x:
+        return 1
+    elif x is None:
+        return 2
+";
+
+#[test]
+fn decision_count_is_the_default()
+{
+    let options = ParserOptions::default();
+    let expected = file_parser::source_complexity("file.py", NESTED, &options);
+
+    assert_eq!(options.algorithm, Algorithm::DecisionCount);
+    assert_eq!(Algorithm::DecisionCount.implementation().complexity("file.py", NESTED, &options), expected);
+    assert_eq!(file_parser::parse_source("file.py", NESTED, &options).map(|(cc, functions, _)| (cc, functions)), expected);
+}
+
+#[test]
+fn mccabe_adds_one_for_each_function()
+{
+    let options = ParserOptions::default();
+
+    /* while, if, and, if, elif, plus the two functions */
+    assert_eq!(Algorithm::Mccabe.implementation().complexity("file.py", NESTED, &options), Some((7.0, 2)));
+    /* code outside of any function is still one path */
+    assert_eq!(Algorithm::Mccabe.implementation().complexity("file.py", "if x:\n    y = 1\n", &options), Some((2.0, 0)));
+    assert_eq!(Algorithm::Mccabe.implementation().complexity("file.json", "{}", &options), Some((0.0, 0)));
+}
+
+#[test]
+fn cognitive_weights_nested_decisions()
+{
+    let options = ParserOptions::default();
+
+    /* the while costs 1, the if nested in it 2 and its and 1, then the if
+     * and elif at the top of g cost 1 each */
+    assert_eq!(Algorithm::Cognitive.implementation().complexity("file.py", NESTED, &options), Some((6.0, 2)));
+}
+
+#[test]
+fn halstead_is_the_difficulty()
+{
+    let options = ParserOptions::default();
+    let difficulty = halstead::source_halstead("file.py", NESTED, &options).unwrap().difficulty();
+
+    assert_eq!(Algorithm::Halstead.implementation().complexity("file.py", NESTED, &options), Some((difficulty, 2)));
+}
+
+#[test]
+fn parsed_files_use_the_chosen_algorithm()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.c");
+    let decisions = cyclo::analyze_file(&path, &ParserOptions::default()).unwrap();
+    let mccabe = cyclo::analyze_file(&path, &ParserOptions { algorithm: Algorithm::Mccabe, ..Default::default() }).unwrap();
+
+    assert_eq!(mccabe.cc, decisions.cc + decisions.functions as f64);
+    assert_eq!((mccabe.functions, mccabe.nloc), (decisions.functions, decisions.nloc));
+}
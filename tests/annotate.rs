@@ -0,0 +1,34 @@
+use cyclo::annotate;
+
+
+#[test]
+fn annotation_goes_after_the_shebang()
+{
+    let annotated = annotate::annotate("#!/usr/bin/env python3\nimport os\n", "# cyclo: cc=1, nloc=1");
+
+    assert_eq!(annotated, "#!/usr/bin/env python3\n# cyclo: cc=1, nloc=1\nimport os\n");
+}
+
+#[test]
+fn annotating_again_replaces_the_annotation()
+{
+    let once = annotate::annotate("int x;", "// cyclo: cc=1, nloc=1");
+    let twice = annotate::annotate(&once, "// cyclo: cc=2, nloc=1");
+
+    assert_eq!(twice, "// cyclo: cc=2, nloc=1\nint x;");
+}
+
+#[test]
+fn line_endings_are_kept()
+{
+    let annotated = annotate::annotate("#!/bin/sh\r\nif true; then\r\n  exit 0\r\nfi\r\n", "# cyclo: cc=1, nloc=3");
+    assert_eq!(annotated, "#!/bin/sh\r\n# cyclo: cc=1, nloc=3\r\nif true; then\r\n  exit 0\r\nfi\r\n");
+
+    /* replacing the annotation keeps the endings too */
+    let again = annotate::annotate(&annotated, "# cyclo: cc=2, nloc=3");
+    assert_eq!(again, annotated.replace("cc=1", "cc=2"));
+
+    /* a file without a trailing newline still doesn't have one */
+    assert_eq!(annotate::annotate("int x;\r\nint y;", "// cyclo: cc=0, nloc=2"), "// cyclo: cc=0, nloc=2\r\nint x;\r\nint y;");
+    assert_eq!(annotate::annotate("#!/bin/sh", "# cyclo: cc=0, nloc=0"), "#!/bin/sh\n# cyclo: cc=0, nloc=0");
+}
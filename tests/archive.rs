@@ -0,0 +1,90 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use cyclo::file_parser::{FileResult, ParserOptions};
+
+
+/// The files in each archive, with a .cycloignore skipping the vendored code
+const FILES: [(&str, &str); 5] = [(".cycloignore", "# not ours\nvendor/\n"),
+                                  ("proj/a.c", "int a(int x)\n{\n    if (x > 0 && x < 9)\n        return 1;\n    return 0;\n}\n"),
+                                  ("proj/b.py", "def b(x):\n    while x:\n        x -= 1\n"),
+                                  ("proj/vendor/v.c", "int v(int x)\n{\n    if (x) return 1;\n    return 0;\n}\n"),
+                                  ("proj/.hidden/h.c", "int h(void) { return 0; }\n")];
+
+/// Write the files into a tar archive
+fn write_tar<W: Write>(writer: W) -> W
+{
+    let mut tar = tar::Builder::new(writer);
+
+    for (name, content) in FILES
+    {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, content.as_bytes()).unwrap();
+    }
+
+    tar.into_inner().unwrap()
+}
+
+/// Write the files into a zip archive
+fn write_zip(path: &Path)
+{
+    let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+
+    for (name, content) in FILES
+    {
+        zip.start_file(name, zip::write::FileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+
+    zip.finish().unwrap();
+}
+
+/// The results without the root of their labels, which is the archive or
+/// directory name
+fn relative(files: &[FileResult]) -> Vec<(String, u64, f64, u64)>
+{
+    let mut files: Vec<(String, u64, f64, u64)> = files.iter()
+                                                       .map(|f| (f.label.split_once('/').unwrap().1.to_string(), f.nloc, f.cc, f.functions))
+                                                       .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    files
+}
+
+#[test]
+fn archives_match_the_extracted_files()
+{
+    let root = std::env::temp_dir().join(format!("cyclo-archive-{}", std::process::id()));
+    let extracted = root.join("extracted");
+
+    for (name, content) in FILES
+    {
+        let path = extracted.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    write_tar(File::create(root.join("files.tar")).unwrap());
+    write_tar(GzEncoder::new(File::create(root.join("files.tar.gz")).unwrap(), Compression::default())).finish().unwrap();
+    write_zip(&root.join("files.zip"));
+
+    let expected = relative(&cyclo::analyze(&extracted, &ParserOptions::default(), 1));
+    let archives: Vec<Vec<FileResult>> = ["files.tar", "files.tar.gz", "files.zip"].iter()
+                                                                                   .map(|n| cyclo::archive::analyze_archive(&root.join(n), &ParserOptions::default()).unwrap())
+                                                                                   .collect();
+    fs::remove_dir_all(&root).unwrap();
+
+    /* the hidden and ignored files are skipped in both */
+    assert_eq!(expected.iter().map(|f| f.0.as_str()).collect::<Vec<_>>(), ["proj/a.c", "proj/b.py"]);
+
+    for files in archives
+    {
+        assert_eq!(relative(&files), expected);
+    }
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use cyclo::baseline;
+use cyclo::file_parser::ParserOptions;
+use cyclo::output::JsonReport;
+
+use common::file;
+
+
+#[test]
+fn increases_beyond_the_tolerance_fail()
+{
+    let before = JsonReport::new(&[file("src/a.c", 10, 4.0), file("src/b.c", 10, 6.0)], &[]);
+    let after = [file("src/a.c", 10, 5.0), file("src/b.c", 10, 6.0), file("src/new.c", 10, 2.0)];
+
+    /* a rose by one, and the new file only counts toward the total */
+    let increases = baseline::increases(&before, &after, 0.0);
+    assert_eq!(increases.iter().map(|r| (r.label.as_str(), r.before, r.after)).collect::<Vec<_>>(), [("src/a.c", Some(4.0), 5.0)]);
+    assert_eq!(baseline::total_increase(&before, &after, 0.0), Some((10.0, 13.0)));
+
+    /* within the tolerance neither fails */
+    assert!(baseline::increases(&before, &after, 1.0).is_empty());
+    assert_eq!(baseline::total_increase(&before, &after, 3.0), None);
+}
+
+#[test]
+fn no_increase_passes()
+{
+    let before = JsonReport::new(&[file("src/a.c", 10, 4.0), file("src/b.c", 10, 6.0)], &[]);
+    let after = [file("src/a.c", 10, 3.0), file("src/b.c", 10, 6.0)];
+
+    assert!(baseline::increases(&before, &after, 0.0).is_empty());
+    assert_eq!(baseline::total_increase(&before, &after, 0.0), None);
+}
+
+#[test]
+fn tolerance_needs_assert_no_increase()
+{
+    let path = std::env::temp_dir().join(format!("cyclo-baseline-{}.json", std::process::id()));
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let run = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_cyclo")).args(["--format", "json", "--path"])
+                                                                       .arg(&fixtures)
+                                                                       .arg("--baseline")
+                                                                       .arg(&path)
+                                                                       .args(args)
+                                                                       .output()
+                                                                       .unwrap()
+                                                                       .status;
+
+    /* one file was one less complex in the baseline */
+    let mut files = cyclo::analyze(&fixtures, &ParserOptions::default(), 1);
+    files[0].cc -= 1.0;
+    baseline::write_baseline(&path, &files).unwrap();
+
+    let rose = run(&["--assert-no-increase"]);
+    let tolerated = run(&["--assert-no-increase", "--tolerance", "1"]);
+    let ignored = run(&["--tolerance", "1"]);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(rose.code(), Some(1));
+    assert!(tolerated.success());
+    /* a tolerance without the ratchet would do nothing, so it's rejected */
+    assert_eq!(ignored.code(), Some(2));
+}
+
+#[test]
+fn single_file_is_checked_against_the_baseline()
+{
+    let path = std::env::temp_dir().join(format!("cyclo-file-baseline-{}.json", std::process::id()));
+    let missing = std::env::temp_dir().join(format!("cyclo-file-missing-{}.json", std::process::id()));
+    let sample = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.c");
+    let run = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_cyclo")).args(["--format", "json", "--path"])
+                                                                       .arg(&sample)
+                                                                       .args(args)
+                                                                       .output()
+                                                                       .unwrap()
+                                                                       .status;
+
+    let written = run(&["--write-baseline", path.to_str().unwrap()]);
+    let unchanged = run(&["--baseline", path.to_str().unwrap()]);
+    let unreadable = run(&["--baseline", missing.to_str().unwrap()]);
+
+    /* the file was one less complex in the baseline */
+    let mut files = vec![cyclo::analyze_file(&sample, &ParserOptions::default()).unwrap()];
+    assert_eq!(baseline::read_baseline(&path).unwrap().files.len(), 1);
+    files[0].cc -= 1.0;
+    baseline::write_baseline(&path, &files).unwrap();
+
+    let rose = run(&["--baseline", path.to_str().unwrap()]);
+    fs::remove_file(&path).unwrap();
+
+    assert!(written.success());
+    assert!(unchanged.success());
+    assert_eq!(unreadable.code(), Some(1));
+    assert_eq!(rose.code(), Some(1));
+}
+
+#[test]
+fn single_file_can_be_ratcheted()
+{
+    let path = std::env::temp_dir().join(format!("cyclo-file-ratchet-{}.json", std::process::id()));
+    let sample = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.c");
+    let run = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_cyclo")).args(["--format", "json", "--path"])
+                                                                       .arg(&sample)
+                                                                       .arg("--baseline")
+                                                                       .arg(&path)
+                                                                       .arg("--assert-no-increase")
+                                                                       .args(args)
+                                                                       .output()
+                                                                       .unwrap()
+                                                                       .status;
+
+    /* the file was one less complex in the baseline */
+    let mut files = vec![cyclo::analyze_file(&sample, &ParserOptions::default()).unwrap()];
+    files[0].cc -= 1.0;
+    baseline::write_baseline(&path, &files).unwrap();
+
+    let rose = run(&[]);
+    let tolerated = run(&["--tolerance", "1"]);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(rose.code(), Some(1));
+    assert!(tolerated.success());
+}
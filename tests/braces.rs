@@ -0,0 +1,97 @@
+use cyclo::braces::BraceTracker;
+
+
+/// Feed each line of some source to a new tracker
+fn track(source: &str) -> BraceTracker
+{
+    let mut tracker = BraceTracker::new();
+
+    for line in source.lines()
+    {
+        tracker.feed(line);
+    }
+
+    tracker
+}
+
+#[test]
+fn braces_in_literals_are_skipped()
+{
+    let tracker = track("void f(void)
+{
+    puts(\"}\");
+    char c = '}';
+    char d = '\\'';
+    puts(\"{ \\\" }\");
+}
+
+int g(void)
+{
+    return 0;
+}
+");
+
+    assert_eq!(tracker.functions, 2);
+    assert_eq!(tracker.spans, vec![(1, 7), (9, 12)]);
+}
+
+#[test]
+fn braces_in_comments_are_skipped()
+{
+    let tracker = track("/* void h(void) { */
+int f(void) // }
+{
+    /* }
+     * } */
+    return 1; // {
+}
+");
+
+    assert_eq!(tracker.functions, 1);
+    assert_eq!(tracker.spans, vec![(2, 7)]);
+}
+
+#[test]
+fn blocks_in_functions_are_not_functions()
+{
+    let tracker = track("int f(int x)
+{
+    if (x)
+    {
+        while (g(x)) { x--; }
+    }
+
+    auto add = [](int a, int b) { return a + b; };
+    return add(x, 1);
+}
+");
+
+    assert_eq!(tracker.functions, 1);
+    assert_eq!(tracker.spans, vec![(1, 10)]);
+}
+
+#[test]
+fn namespaces_and_types_are_not_functions()
+{
+    let tracker = track("namespace outer
+{
+    struct point { int x; int y; };
+    int values[] = { 1, 2 };
+
+    namespace inner
+    {
+        int f(void) { return 0; }
+    }
+}
+
+extern \"C\"
+{
+    void g(void)
+    {
+    }
+}
+");
+
+    assert_eq!(tracker.functions, 2);
+    assert_eq!(tracker.spans, vec![(8, 8), (14, 16)]);
+}
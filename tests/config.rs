@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use cyclo::config::{Config, LanguageConfig};
+use cyclo::file_parser::{self, ParserOptions};
+
+
+/// Parse a file in tests/fixtures with the keywords from the fixture config
+fn parse_fixture(name: &str) -> (f64, u64, u64)
+{
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let config = Config::load(&fixtures.join("cyclo.toml")).unwrap();
+    let options = ParserOptions { custom_keywords: config.keywords(), ..Default::default() };
+    let file = cyclo::analyze_file(&fixtures.join(name), &options).unwrap();
+
+    (file.cc, file.functions, file.nloc)
+}
+
+#[test]
+fn configured_extension_is_parsed()
+{
+    /* two when statements and an and, with the commented one skipped */
+    assert_eq!(parse_fixture("sample.dsl"), (3.0, 1, 4));
+}
+
+#[test]
+fn configured_keywords_override_builtin_ones()
+{
+    /* the for, while and except aren't statements any more, but the and and
+     * the function definitions are kept */
+    assert_eq!(parse_fixture("sample.py"), (2.0, 3, 21));
+}
+
+#[test]
+fn configured_language_can_be_case_insensitive()
+{
+    /* the keywords are configured in title case and used in any case, and
+     * the If in the comment is skipped */
+    assert_eq!(parse_fixture("sample.bas"), (3.0, 1, 6));
+}
+
+#[test]
+fn builtin_case_insensitivity_can_be_turned_off()
+{
+    let source = std::fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.sql")).unwrap();
+    let language = LanguageConfig { case_insensitive: Some(false), ..Default::default() };
+    let config = Config { languages: HashMap::from([("sql".to_string(), language)]) };
+    let options = ParserOptions { custom_keywords: config.keywords(), ..Default::default() };
+
+    /* every keyword in the fixture is uppercase */
+    assert_eq!(file_parser::parse_source("sample.sql", &source, &options).unwrap().0, 0.0);
+    assert_eq!(file_parser::parse_source("sample.sql", &source, &ParserOptions::default()).unwrap().0, 8.0);
+}
+
+#[test]
+fn overlapping_function_defs_dont_hide_more_ops_than_they_cover()
+{
+    let language = LanguageConfig
+    {
+        logical_ops: Some(vec!["or".to_string()]),
+        function_def: Some(vec!["create or".to_string(), "or replace".to_string()]),
+        ..Default::default()
+    };
+    let config = Config { languages: HashMap::from([("sql".to_string(), language)]) };
+    let options = ParserOptions { custom_keywords: config.keywords(), ..Default::default() };
+
+    /* the one OR covered by both definitions isn't counted, the other is */
+    let source = "CREATE OR REPLACE FUNCTION f() AS $$\nSELECT a OR b;\n$$;\n";
+    assert_eq!(file_parser::parse_source("query.sql", source, &options).unwrap().0, 1.0);
+}
@@ -0,0 +1,89 @@
+use cyclo::diff::{self, DiffResult};
+use cyclo::file_parser::ParserOptions;
+
+
+/// A diff adding a C file, deleting a Python file, changing another C file,
+/// and touching a file in an unsupported language
+const DIFF: &str = "diff --git a/new.c b/new.c
+new file mode 100644
+--- /dev/null
++++ b/new.c
+@@ -0,0 +1,4 @@
++int f(int a, int b)
++{
++    if (a && b) return 1;
++}
+diff --git a/old.py b/old.py
+deleted file mode 100644
+--- a/old.py
++++ /dev/null
+@@ -1,2 +0,0 @@
+-if x:
+-    y = 1
+diff --git a/main.c b/main.c
+--- a/main.c\t2024-01-01 00:00:00
++++ b/main.c\t2024-01-02 00:00:00
+@@ -1,5 +1,6 @@
+ int g(int a, int b, int c)
+ {
+-    if (a)
+---c;
++    if (a || b)
++        while (c) c--;
++++c;
+ }
+diff --git a/notes.unknown b/notes.unknown
+--- a/notes.unknown
++++ b/notes.unknown
+@@ -1 +1 @@
+-if
++if while
+";
+
+/// Analyze the diff and find the result for a file
+fn result(file: &str) -> DiffResult
+{
+    diff::analyze_diff(DIFF, &ParserOptions::default()).into_iter()
+                                                       .find(|r| r.file == file)
+                                                       .unwrap()
+}
+
+#[test]
+fn unsupported_files_are_left_out()
+{
+    let files: Vec<String> = diff::analyze_diff(DIFF, &ParserOptions::default()).into_iter()
+                                                                                .map(|r| r.file)
+                                                                                .collect();
+
+    assert_eq!(files, vec!["new.c", "old.py", "main.c"]);
+}
+
+#[test]
+fn added_files_only_add_complexity()
+{
+    let added = result("new.c");
+
+    /* the if and the && */
+    assert_eq!((added.added, added.removed), (2.0, 0.0));
+    assert_eq!(added.delta(), 2.0);
+}
+
+#[test]
+fn removed_files_are_named_by_their_old_path()
+{
+    let removed = result("old.py");
+
+    assert_eq!((removed.added, removed.removed), (0.0, 1.0));
+    assert_eq!(removed.delta(), -1.0);
+}
+
+#[test]
+fn changed_files_count_both_sides()
+{
+    let changed = result("main.c");
+
+    /* the lines starting with --- and +++ inside the hunk are code, not
+     * file headers */
+    assert_eq!((changed.added, changed.removed), (3.0, 1.0));
+    assert_eq!(changed.delta(), 2.0);
+}
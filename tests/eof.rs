@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use cyclo::file_parser::{FileResult, ParserOptions};
+use cyclo::functions;
+
+
+/// Parse a file in tests/fixtures, with or without joining logical lines
+fn parse_fixture(name: &str, logical_lines: bool) -> FileResult
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    let options = ParserOptions { logical_lines, ..Default::default() };
+
+    cyclo::analyze_file(&path, &options).unwrap()
+}
+
+#[test]
+fn last_line_without_newline_is_counted()
+{
+    for logical_lines in [false, true]
+    {
+        let file = parse_fixture("no_newline.c", logical_lines);
+
+        assert_eq!(file.cc, 2.0);
+        assert_eq!(file.functions, 1);
+        assert_eq!(file.nloc, 8);
+    }
+}
+
+#[test]
+fn last_function_without_newline_has_a_snippet()
+{
+    let file = parse_fixture("no_newline.c", false);
+    let snippets = functions::worst_functions(&[file], 1, &ParserOptions::default());
+
+    assert_eq!(snippets.len(), 1);
+    assert_eq!((snippets[0].start, snippets[0].end), (1, 8));
+    assert!(snippets[0].source.ends_with('}'));
+}
+
+#[test]
+fn unterminated_block_comment_runs_to_eof()
+{
+    for logical_lines in [false, true]
+    {
+        /* the if inside the comment isn't counted, nor its braces */
+        let file = parse_fixture("unterminated_comment.c", logical_lines);
+
+        assert_eq!(file.cc, 1.0);
+        assert_eq!(file.functions, 1);
+    }
+}
+
+#[test]
+fn unbalanced_paren_at_eof_is_counted()
+{
+    /* the last logical line is still open at the end of the file */
+    let physical = parse_fixture("unbalanced_paren.py", false);
+    let logical = parse_fixture("unbalanced_paren.py", true);
+
+    assert_eq!(physical.cc, 4.0);
+    assert_eq!(logical.cc, 4.0);
+}
+
+#[test]
+fn functions_after_invalid_utf8_have_snippets()
+{
+    /* a Latin-1 comment in the first function */
+    let path = std::env::temp_dir().join(format!("cyclo-latin1-{}.c", std::process::id()));
+    let mut content = b"int f(int x)\n{\n    /* caf".to_vec();
+    content.extend_from_slice(b"\xe9 */\n    return x;\n}\n\nint g(int x)\n{\n    if (x) return 1;\n    return 0;\n}\n");
+    std::fs::write(&path, content).unwrap();
+
+    let file = cyclo::analyze_file(&path, &ParserOptions::default()).unwrap();
+    let snippets = functions::file_snippets(&file, &ParserOptions::default());
+    std::fs::remove_file(&path).unwrap();
+
+    let spans: Vec<(usize, usize)> = snippets.iter().map(|s| (s.start, s.end)).collect();
+    assert_eq!(file.functions, 2);
+    assert_eq!(spans, [(1, 5), (7, 11)]);
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::Path;
+
+use cyclo::exclude::ExcludePatterns;
+use cyclo::file_parser::ParserOptions;
+
+
+/// Compile some patterns, which are all valid
+fn patterns(patterns: &[&str]) -> ExcludePatterns
+{
+    let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+
+    ExcludePatterns::new(&patterns).unwrap()
+}
+
+#[test]
+fn negated_patterns_include_files_again()
+{
+    let exclude = patterns(&["*.c", "!keep.c"]);
+
+    assert!(exclude.is_excluded(Path::new("src/drop.c"), false));
+    assert!(!exclude.is_excluded(Path::new("src/keep.c"), false));
+    assert!(!exclude.is_excluded(Path::new("src/main.rs"), false));
+
+    /* the last matching pattern wins */
+    let exclude = patterns(&["!keep.c", "*.c"]);
+
+    assert!(exclude.is_excluded(Path::new("src/keep.c"), false));
+}
+
+#[test]
+fn negated_patterns_include_files_in_excluded_dirs()
+{
+    let exclude = patterns(&["vendor/", "!vendor/local/"]);
+
+    assert!(exclude.is_excluded(Path::new("vendor/lib/a.c"), false));
+    assert!(!exclude.is_excluded(Path::new("vendor/local/a.c"), false));
+
+    /* vendor has to be walked to find vendor/local, but vendor/lib doesn't */
+    assert!(!exclude.is_pruned(Path::new("vendor")));
+    assert!(exclude.is_pruned(Path::new("vendor/lib")));
+    assert!(!exclude.is_pruned(Path::new("vendor/local")));
+}
+
+#[test]
+fn excluded_dirs_are_pruned()
+{
+    let exclude = patterns(&["build/", "*.o"]);
+
+    assert!(exclude.is_pruned(Path::new("build")));
+    assert!(exclude.is_pruned(Path::new("src/build")));
+    assert!(exclude.is_excluded(Path::new("build/main.c"), false));
+    assert!(!exclude.is_pruned(Path::new("src")));
+
+    /* a directory only pattern doesn't match a file with the same name */
+    assert!(!exclude.is_excluded(Path::new("src/build"), false));
+}
+
+#[test]
+fn dirs_are_not_pruned_when_a_negation_could_match_inside()
+{
+    let exclude = patterns(&["generated/", "!*.c"]);
+
+    assert!(!exclude.is_pruned(Path::new("generated")));
+    assert!(!exclude.is_excluded(Path::new("generated/table.c"), false));
+    assert!(exclude.is_excluded(Path::new("generated/table.h"), false));
+}
+
+#[test]
+fn walk_skips_pruned_dirs_and_keeps_negated_files()
+{
+    let root = std::env::temp_dir().join(format!("cyclo-exclude-{}", std::process::id()));
+    fs::create_dir_all(root.join("vendor/lib")).unwrap();
+    fs::create_dir_all(root.join("vendor/local")).unwrap();
+    fs::write(root.join("a.c"), "int a(int x) { return x; }\n").unwrap();
+    fs::write(root.join("vendor/lib/b.c"), "int b(int x) { return x; }\n").unwrap();
+    fs::write(root.join("vendor/local/c.c"), "int c(int x) { return x; }\n").unwrap();
+    fs::write(root.join("vendor/local/d.c"), "int d(int x) { return x; }\n").unwrap();
+
+    let options = ParserOptions { exclude: patterns(&["vendor/", "!vendor/local/", "d.c"]), ..Default::default() };
+    let mut walked: Vec<String> = cyclo::walk(&root, &options).map(|e| e.path().strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+                                                              .collect();
+    fs::remove_dir_all(&root).unwrap();
+    walked.sort();
+
+    assert_eq!(walked, vec!["a.c", "vendor/local/c.c"]);
+}
@@ -0,0 +1,31 @@
+int wait_ready(volatile int *status)
+{
+    int ready;
+
+    __asm__ volatile (
+        "1: movl (%1), %0\n\t"
+        "testl %0, %0\n\t"
+        "jz 1b\n\t"
+        "cmpl $2, %0\n\t"
+        "jne 2f\n\t"
+        "2:"
+        : "=r" (ready)
+        : "r" (status));
+
+    return ready;
+}
+
+void spin(void)
+{
+    __asm
+    {
+    again:
+        dec ecx
+        jnz again
+    }
+
+    asm("nop");
+    /* the jmp and loop in a variable name outside of asm don't count */
+    int jmp_count = 0, loop = 1;
+    if (loop) jmp_count++;
+}
@@ -0,0 +1,15 @@
+data Shape
+  = Circle Double
+  | Square Double
+  | Rect Double Double
+
+area :: Shape -> Double
+area s
+  | isBig s = 0
+  | otherwise = case s of
+      Circle r -> pi * r * r
+      Square w -> w * w
+      Rect w h -> w * h
+
+scale :: [Int] -> [Int]
+scale = map (\x -> if x > 0 then x * 2 else x)
@@ -0,0 +1,22 @@
+/* cyclo:ignore-start */
+static int generated(int op)
+{
+    switch (op)
+    {
+        case 1: return 2;
+        case 2: return 3;
+    }
+    return 0;
+}
+/* cyclo:ignore-end */
+
+static int identity(int x) { return x; } // cyclo:ignore
+
+int parse(const char *s, int x)
+{
+    if (strcmp(s, "cyclo:ignore") == 0)
+        return 1;
+    if (x < 0 && x != -1)
+        return 0;
+    return x;
+}
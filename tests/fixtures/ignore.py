@@ -0,0 +1,19 @@
+def lookup(op):
+    # cyclo:ignore-start
+    if op == "add":
+        return 1
+    elif op == "sub":
+        return 2
+    elif op == "mul":
+        return 3
+    # cyclo:ignore-end
+    if op and op.startswith("x"):
+        return 0
+    return -1
+
+
+def generated(x):
+    if x: return 1  # cyclo:ignore
+    while x > 0:
+        x -= 1
+    return x
@@ -0,0 +1,8 @@
+int clamp(int n)
+{
+    if (n > 10 || n < 0)
+    {
+        return 0;
+    }
+    return n;
+}
\ No newline at end of file
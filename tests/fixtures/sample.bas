@@ -0,0 +1,7 @@
+' IF this comment were counted it would add complexity
+PROCEDURE Check(n)
+    If n > 0 AND n < 10 Then Print "small"
+    while n > 0
+        n = n - 1
+    Wend
+END
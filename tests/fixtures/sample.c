@@ -0,0 +1,25 @@
+#include <stdio.h>
+
+/* returns the sign of a number */
+static int sign(int n)
+{
+    if (n > 0 && n != 0)
+    {
+        return 1;
+    }
+    else if (n < 0)
+    {
+        return -1;
+    }
+
+    return 0;
+}
+
+void count(int n)
+{
+    for (int i = 0; i < n; i++)
+    {
+        // print the sign of each number
+        printf("%d\n", sign(i));
+    }
+}
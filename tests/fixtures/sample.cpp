@@ -0,0 +1,40 @@
+#include <string>
+#include <vector>
+
+namespace sample
+{
+
+class Counter
+{
+public:
+    int total(const std::vector<int> &values) const;
+};
+
+int Counter::total(const std::vector<int> &values) const
+{
+    int total = 0;
+
+    for (int value : values)
+    {
+        if (value > 0 || value < -10)
+        {
+            total += value;
+        }
+    }
+
+    return total;
+}
+
+int parse(const std::string &text)
+{
+    try
+    {
+        return std::stoi(text);
+    }
+    catch (const std::exception &)
+    {
+        return 0;
+    }
+}
+
+}
@@ -0,0 +1,17 @@
+defmodule Sample do
+  @moduledoc """
+  if this heredoc were counted it would add complexity
+  """
+
+  # the sign of a number
+  def sign(n) do
+    cond do
+      n > 0 and n != 0 -> 1
+      true -> -1
+    end
+  end
+
+  defp describe(n) do
+    if n > 0, do: "positive", else: "other"
+  end
+end
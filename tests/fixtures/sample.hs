@@ -0,0 +1,25 @@
+module Sample (sign, describe) where
+
+import Data.List (sort)
+
+{- if this comment were counted
+   it would add complexity -}
+
+-- | The sign of a number
+sign :: Int -> Int
+sign n
+  | n > 0 && n /= 0 = 1
+  | n < 0 = -1
+  | otherwise = 0
+
+describe :: Maybe Int -> String
+describe value =
+  case value of
+    Just n -> if n > 0 then "positive" else "other"
+    Nothing -> "nothing"
+  where
+    diff = map (\x -> x + 1) [1, 2]
+
+total :: [Int] -> Int
+total [] = 0
+total (x:xs) = x + total xs
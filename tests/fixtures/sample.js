@@ -0,0 +1,27 @@
+/* a sample module
+ * if this comment were counted it would add complexity */
+function sign(n) {
+    if (n > 0 && n !== 0) {
+        return 1;
+    }
+    return -1;
+}
+
+function count(n) {
+    let total = 0;
+    for (let i = 0; i < n; i++) {
+        // add the sign
+        total += sign(i);
+    }
+    return total;
+}
+
+function parse(text) {
+    try {
+        return JSON.parse(text);
+    } catch (e) {
+        return null;
+    } finally {
+        console.log(text);
+    }
+}
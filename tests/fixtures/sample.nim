@@ -0,0 +1,25 @@
+#[ if this comment were counted
+   it would add complexity ]#
+
+type
+  Shape = object of RootObj
+    sides: int
+
+# the sign of a number
+proc sign(n: int): int =
+  if n > 0 and n != 0:
+    result = 1
+  elif n < 0:
+    result = -1
+  else:
+    result = 0
+
+func describe(shape: Shape): string =
+  case shape.sides
+  of 3: "triangle"
+  of 4: "square"
+  else: "other"
+
+proc notify(errors: seq[string]) =
+  for error in errors:
+    echo format(error)
@@ -0,0 +1,29 @@
+#!/usr/bin/perl
+use strict;
+use warnings;
+
+=pod
+
+if this documentation were counted it would add complexity
+
+=cut
+
+# the sign of a number
+sub sign {
+    my ($n) = @_;
+    if ($n > 0 && $n != 0) {
+        return 1;
+    } elsif ($n < 0) {
+        return -1;
+    }
+    return 0;
+}
+
+sub count {
+    my ($n) = @_;
+    my ($total, $format, $diff) = (0, "%d", 0);
+    foreach my $i (0 .. $n) {
+        $total += sign($i) unless $i == 0;
+    }
+    return $total;
+}
@@ -0,0 +1,28 @@
+"""A sample module.
+
+if this docstring were counted it would add complexity
+"""
+
+
+def sign(n):
+    # the sign of a number
+    if n > 0 and n != 0:
+        return 1
+    return -1
+
+
+def count(n):
+    total = 0
+    for i in range(n):
+        while total < i:
+            total += sign(i)
+    return total
+
+
+def parse(text):
+    try:
+        return int(text)
+    except ValueError:
+        return None
+    finally:
+        print(text)
@@ -0,0 +1,34 @@
+-- if this comment were counted it would add complexity
+
+/* neither would
+   WHEN this block comment */
+
+CREATE OR REPLACE FUNCTION grade(score INTEGER) RETURNS TEXT AS $$
+BEGIN
+    IF score >= 90 AND score <= 100 THEN
+        RETURN 'A';
+    ELSIF score >= 80 THEN
+        RETURN 'B';
+    END IF;
+    RETURN 'C';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE PROCEDURE drain(batch INTEGER)
+AS $$
+BEGIN
+    WHILE batch > 0 LOOP
+        batch := batch - 1;
+    END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT id,
+       CASE
+           WHEN total > 100 OR priority THEN 'high'
+           WHEN total > 10 THEN 'medium'
+           ELSE 'low'
+       END AS bucket,
+       ordered
+FROM orders
+WHERE shipped IS NULL;
@@ -0,0 +1,22 @@
+const std = @import("std");
+
+// the sign of a number
+fn sign(n: i32) i32 {
+    if (n > 0 and n != 0) {
+        return 1;
+    } else {
+        return -1;
+    }
+}
+
+pub fn count(n: i32) i32 {
+    var total: i32 = 0;
+    var i: i32 = 0;
+    while (i < n) : (i += 1) {
+        total += switch (sign(i)) {
+            1 => 1,
+            else => 0,
+        };
+    }
+    return total;
+}
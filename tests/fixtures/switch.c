@@ -0,0 +1,25 @@
+/* a switch nested in a switch, with labels sharing a body */
+int classify(int kind, int sub)
+{
+    switch (kind)
+    {
+    case 0:
+    case 1: case 2:
+        return 0;
+    case 3:
+        switch (sub)
+        {
+        case 'a':
+            puts("case 'b':");
+            break;
+        default:
+            break;
+        }
+        break;
+    default:
+        return -1;
+    }
+
+    // case 4: is only mentioned in a comment
+    return lowercase(kind);
+}
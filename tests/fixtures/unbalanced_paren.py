@@ -0,0 +1,5 @@
+def sign(n):
+    if (n > 0 and
+            n != 0):
+        return 1
+    return -1 if (n < 0 and
\ No newline at end of file
@@ -0,0 +1,14 @@
+int sign(int n)
+{
+    if (n > 0)
+    {
+        return 1;
+    }
+    return 0;
+}
+
+/* the rest of the file was cut off
+if (n < 0)
+{
+    return -1;
+}
\ No newline at end of file
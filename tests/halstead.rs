@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+use cyclo::file_parser::ParserOptions;
+use cyclo::halstead::{self, Halstead};
+
+
+#[test]
+fn operators_and_operands_are_counted()
+{
+    /* operators: if ( > { = + ; = ; and the comment line is stripped.
+     * operands: x 0 y x 1 y "done" */
+    let source = "if (x > 0) { y = x + 1; }\n// y = 2\ny = \"done\";\n";
+    let halstead = halstead::source_halstead("file.c", source, &ParserOptions::default()).unwrap();
+
+    assert_eq!(halstead, Halstead { distinct_operators: 7, distinct_operands: 5, operators: 9, operands: 7 });
+    assert_eq!(halstead.vocabulary(), 12);
+    assert_eq!(halstead.length(), 16);
+    assert_eq!(halstead.volume(), 16.0 * 12f64.log2());
+    assert!((halstead.difficulty() - 4.9).abs() < 1e-9);
+}
+
+#[test]
+fn keywords_are_operators_and_longest_symbols_win()
+{
+    /* and, not, def and += are each one operator */
+    let source = "def f(a, b):\n    a += b and not a\n";
+    let halstead = halstead::source_halstead("file.py", source, &ParserOptions::default()).unwrap();
+
+    /* def ( , : += and not, then f a b a b a */
+    assert_eq!((halstead.distinct_operators, halstead.operators), (7, 7));
+    assert_eq!((halstead.distinct_operands, halstead.operands), (3, 6));
+}
+
+#[test]
+fn empty_and_unsupported_files()
+{
+    let halstead = halstead::source_halstead("file.c", "", &ParserOptions::default()).unwrap();
+    assert_eq!((halstead.volume(), halstead.difficulty()), (0.0, 0.0));
+
+    assert!(halstead::source_halstead("file.rs", "fn main() {}", &ParserOptions::default()).is_none());
+}
+
+#[test]
+fn parsed_files_are_read_again()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.c");
+    let file = cyclo::analyze_file(&path, &ParserOptions::default()).unwrap();
+    let source = std::fs::read_to_string(&path).unwrap();
+
+    assert_eq!(halstead::file_halstead(&file, &ParserOptions::default()), halstead::source_halstead("sample.c", &source, &ParserOptions::default()));
+}
@@ -0,0 +1,20 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use cyclo::file_parser::ParserOptions;
+use cyclo::hotspots;
+
+
+#[test]
+fn hotspots_are_ranked_by_complexity_times_commits()
+{
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").canonicalize().unwrap();
+    let files = ["sample.c", "sample.py", "sample.js"].map(|name| cyclo::analyze_file(&fixtures.join(name), &ParserOptions::default()).unwrap());
+
+    /* sample.js never changed, so it isn't a hotspot */
+    let churn = HashMap::from([(fixtures.join("sample.c"), 3), (fixtures.join("sample.py"), 1)]);
+    let ranked = hotspots::hotspots(&files, &churn);
+
+    let scores: Vec<(&str, f64)> = ranked.iter().map(|h| (h.label.as_str(), h.score)).collect();
+    assert_eq!(scores, [("sample.c", 12.0), ("sample.py", 5.0)]);
+}
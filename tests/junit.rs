@@ -0,0 +1,33 @@
+mod common;
+
+use cyclo::output;
+
+use common::file;
+
+
+#[test]
+fn files_above_the_threshold_fail()
+{
+    let files = [file("src/simple.c", 10, 3.0), file("src/<tangled> & \"odd\".c", 10, 25.0), file("src/edge.c", 10, 20.0)];
+    let xml = output::junit(&files, 20.0);
+
+    assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+    assert!(xml.contains("<testsuite name=\"complexity\" tests=\"3\" failures=\"1\">"));
+
+    /* a file at the threshold passes, like the exit code gate */
+    assert!(xml.contains("<testcase classname=\"src\" name=\"src/simple.c\"/>"));
+    assert!(xml.contains("<testcase classname=\"src\" name=\"src/edge.c\"/>"));
+
+    /* the name is escaped, and the failure says by how much */
+    assert!(xml.contains("<testcase classname=\"src\" name=\"src/&lt;tangled&gt; &amp; &quot;odd&quot;.c\">\n            <failure type=\"complexity\" message=\"src/&lt;tangled&gt; &amp; &quot;odd&quot;.c has a complexity of 25 over 10 lines of code, which exceeds the threshold of 20\"/>\n        </testcase>"));
+    assert!(xml.ends_with("    </testsuite>\n</testsuites>"));
+}
+
+#[test]
+fn control_characters_are_replaced()
+{
+    let xml = output::junit(&[file("src/\u{1}.c", 10, 1.0)], 20.0);
+
+    assert!(xml.contains("name=\"src/\u{fffd}.c\""));
+    assert!(!xml.contains('\u{1}'));
+}
@@ -0,0 +1,208 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use cyclo::file_parser::{FileResult, ParserOptions};
+use cyclo::functions;
+use cyclo::treemap::{self, GroupBy, Treemap};
+
+use common::file;
+
+
+#[test]
+fn labels_use_forward_slashes()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let files = cyclo::analyze(&path, &ParserOptions::default(), 1);
+    let file = files.iter().find(|f| f.label.ends_with("sample.c")).unwrap();
+
+    assert_eq!(file.label, "fixtures/sample.c");
+    assert_eq!(file.parent, "fixtures");
+}
+
+#[test]
+fn labels_are_rooted_at_relative_to()
+{
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).canonicalize().unwrap();
+    let options = ParserOptions { relative_to: Some(root.clone()), ..Default::default() };
+    let file = cyclo::analyze_file(&root.join("tests/fixtures/sample.c"), &options).unwrap();
+
+    assert_eq!(file.label, "tests/fixtures/sample.c");
+    assert_eq!(file.parent, "tests/fixtures");
+}
+
+#[test]
+fn file_outside_relative_to_is_an_error()
+{
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).canonicalize().unwrap();
+    let options = ParserOptions { relative_to: Some(root.join("src")), ..Default::default() };
+
+    assert!(cyclo::analyze_file(&root.join("tests/fixtures/sample.c"), &options).is_err());
+}
+
+#[test]
+fn failures_are_collected()
+{
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).canonicalize().unwrap();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let options = ParserOptions { relative_to: Some(root.join("src")), errors: Some(Arc::clone(&errors)), ..Default::default() };
+
+    /* every file fails, and each failure is collected rather than printed */
+    let files = cyclo::analyze(&root.join("tests/fixtures"), &options, 2);
+    let errors = errors.lock().unwrap();
+
+    assert!(files.is_empty());
+    assert_eq!(errors.len(), cyclo::walk(&root.join("tests/fixtures"), &options).count());
+    assert!(errors.iter().all(|e| e.kind() == "NotUnderPrefix" && e.file().starts_with(root.to_str().unwrap())));
+}
+
+#[test]
+fn walk_failures_are_collected()
+{
+    let missing = std::env::temp_dir().join(format!("cyclo-missing-{}", std::process::id()));
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let options = ParserOptions { errors: Some(Arc::clone(&errors)), ..Default::default() };
+
+    /* a path which can't be walked is a failure, not a panic */
+    let files = cyclo::analyze(&missing, &options, 1);
+    let errors = errors.lock().unwrap();
+
+    assert!(files.is_empty());
+    assert_eq!(errors.len(), 1);
+    assert_eq!((errors[0].kind(), errors[0].file()), ("Walk", missing.to_str().unwrap()));
+}
+
+#[test]
+fn files_are_grouped_by_language()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let files = cyclo::analyze(&path, &ParserOptions::default(), 1);
+    let treemap = Treemap::from_files_grouped(&files, GroupBy::Language);
+
+    let parent = |label: &str| treemap.labels.iter().position(|l| l == label).map(|i| treemap.parents[i].as_str());
+
+    assert_eq!(parent("c"), Some(""));
+    assert_eq!(parent("c/fixtures"), Some("c"));
+    assert_eq!(parent("c/fixtures/sample.c"), Some("c/fixtures"));
+    assert_eq!(parent("py/fixtures/sample.py"), Some("py/fixtures"));
+
+    /* every file is still in the treemap once */
+    assert_eq!(treemap.nlocs.iter().sum::<u64>(), files.iter().map(|f| f.nloc).sum::<u64>());
+}
+
+#[test]
+fn functions_are_added_under_their_file()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.c");
+    let files = [cyclo::analyze_file(&path, &ParserOptions::default()).unwrap()];
+    let mut treemap = Treemap::from_files(&files);
+    treemap.add_functions(&functions::file_snippets(&files[0], &ParserOptions::default()), &ParserOptions::default());
+
+    let children: Vec<(&str, u64)> = treemap.labels.iter()
+                                                   .zip(&treemap.parents)
+                                                   .zip(&treemap.nlocs)
+                                                   .filter(|((_, parent), _)| *parent == "sample.c")
+                                                   .map(|((label, _), nloc)| (label.as_str(), *nloc))
+                                                   .collect();
+
+    /* the blank line in sign and the comment in count aren't code, so the
+     * #include is what's left of the file */
+    assert_eq!(children, [("sample.c:4", 12), ("sample.c:18", 7)]);
+    assert_eq!(treemap.nlocs[treemap.labels.iter().position(|l| l == "sample.c").unwrap()], 1);
+}
+
+#[test]
+fn some_paths_are_labelled_like_the_walk()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let walked = cyclo::analyze(&path, &ParserOptions::default(), 1);
+
+    /* a deleted file and one which can't be parsed are skipped */
+    let relative = ["sample.py", "deleted.c", "cyclo.toml", "sample.c"].map(PathBuf::from);
+    let files = cyclo::analyze_paths(&path, &relative, &ParserOptions::default());
+
+    let labels: Vec<&str> = files.iter().map(|f| f.label.as_str()).collect();
+    assert_eq!(labels, ["fixtures/sample.c", "fixtures/sample.py"]);
+
+    for file in &files
+    {
+        let expected = walked.iter().find(|f| f.label == file.label).unwrap();
+        assert_eq!((&file.parent, file.cc, file.nloc), (&expected.parent, expected.cc, expected.nloc));
+    }
+}
+
+#[test]
+fn listed_dirs_are_labelled_with_their_path()
+{
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let list = root.join("tests/fixtures/dirs.list");
+
+    assert!(cyclo::is_path_list(&list));
+    assert_eq!(cyclo::read_path_list(&list).unwrap(), [root.join("tests/fixtures/.")]);
+
+    let files = cyclo::analyze_dirs(&root, &[root.join("tests/fixtures")], &ParserOptions::default(), 1);
+    let file = files.iter().find(|f| f.label.ends_with("sample.c")).unwrap();
+
+    assert_eq!(file.label, "tests/fixtures/sample.c");
+    assert_eq!(file.parent, "tests/fixtures");
+}
+
+#[cfg(unix)]
+#[test]
+fn names_which_arent_utf8_are_converted_lossily()
+{
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = std::env::temp_dir().join(format!("cyclo-latin1-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    /* caf\xe9.c, with the é in latin-1 */
+    fs::copy(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.c"), dir.join(OsStr::from_bytes(b"caf\xe9.c"))).unwrap();
+
+    let files = cyclo::analyze(&dir, &ParserOptions::default(), 1);
+    let file = cyclo::analyze_file(&dir.join(OsStr::from_bytes(b"caf\xe9.c")), &ParserOptions::default());
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(files.len(), 1);
+    assert!(files[0].label.ends_with("/caf\u{fffd}.c"));
+    assert_eq!(files[0].cc, 4.0);
+    assert_eq!(file.unwrap().label, "caf\u{fffd}.c");
+}
+
+#[test]
+fn file_nodes_have_their_language()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let files = cyclo::analyze(&path, &ParserOptions::default(), 1);
+    let treemap = Treemap::from_files(&files);
+
+    let language = |label: &str| treemap.labels.iter().position(|l| l == label).map(|i| treemap.languages[i].as_str());
+
+    assert_eq!(language("fixtures/sample.c"), Some("c"));
+    assert_eq!(language("fixtures/sample.ex"), Some("elixir"));
+    assert_eq!(language("fixtures"), Some(""));
+
+    let report = cyclo::output::JsonReport::new(&files, &[]);
+    assert!(report.files.iter().zip(&files).all(|(json, file)| json.language == file.language && !json.language.is_empty()));
+}
+
+#[test]
+fn deep_files_are_collapsed_into_their_ancestor()
+{
+    let python = FileResult { language: "py".to_string(), ..file("root/lib/deep/c.py", 30, 6.0) };
+    let files = [file("root/a.c", 10, 1.0), file("root/lib/b.c", 20, 2.0), python, file("root/other/d.c", 5, 3.0)];
+    let collapsed = treemap::collapse_files(&files, 2);
+
+    let nodes: Vec<(&str, &str, u64, f64, u64, &str)> = collapsed.iter()
+                                                                 .map(|f| (f.label.as_str(), f.parent.as_str(), f.nloc, f.cc, f.functions, f.language.as_str()))
+                                                                 .collect();
+    assert_eq!(nodes, [("root/a.c", "root", 10, 1.0, 1, "c"), ("root/lib", "root", 50, 4.0, 2, "mixed"), ("root/other", "root", 5, 3.0, 1, "c")]);
+    assert_eq!(collapsed[1].path, PathBuf::from("root/lib"));
+
+    /* the treemap is only two levels deep, with every line still counted */
+    let treemap = Treemap::from_files(&collapsed);
+    assert!(treemap.labels.iter().all(|l| l.split('/').count() <= 2));
+    assert_eq!(treemap.nlocs.iter().sum::<u64>(), 65);
+}
@@ -0,0 +1,331 @@
+use std::path::PathBuf;
+
+use cyclo::file_parser::{self, FileResult, ParserOptions};
+
+
+/// Every extension cyclo parses for complexity
+const SUPPORTED_EXTENSIONS: [&str; 18] = ["c", "cpp", "cc", "cxx", "py", "js", "mjs", "cjs", "ex", "exs", "zig", "pl", "pm", "hs", "nim", "vue", "svelte", "sql"];
+
+/// Parse a file in tests/fixtures with the default options
+fn parse_fixture(name: &str) -> FileResult
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+
+    cyclo::analyze_file(&path, &ParserOptions::default()).unwrap()
+}
+
+/// Check the complexity, number of functions, and lines of code of a fixture
+fn assert_fixture(name: &str, cc: f64, functions: u64, nloc: u64)
+{
+    let file = parse_fixture(name);
+
+    assert_eq!(file.cc, cc, "{} complexity", name);
+    assert_eq!(file.functions, functions, "{} functions", name);
+    assert_eq!(file.nloc, nloc, "{} nloc", name);
+}
+
+#[test]
+fn c_fixture()
+{
+    assert_fixture("sample.c", 4.0, 2, 20);
+}
+
+#[test]
+fn cpp_fixture()
+{
+    /* the method declaration in the class body isn't a function */
+    assert_fixture("sample.cpp", 4.0, 2, 33);
+}
+
+#[test]
+fn python_fixture()
+{
+    /* the "or" logical op is a whole word, so it doesn't match "for" or
+     * "ValueError", and finally isn't counted */
+    assert_fixture("sample.py", 5.0, 3, 21);
+}
+
+#[test]
+fn javascript_fixture()
+{
+    /* catch is counted but finally isn't */
+    assert_fixture("sample.js", 4.0, 3, 22);
+}
+
+#[test]
+fn elixir_fixture()
+{
+    assert_fixture("sample.ex", 5.0, 2, 14);
+}
+
+#[test]
+fn zig_fixture()
+{
+    assert_fixture("sample.zig", 7.0, 2, 19);
+}
+
+#[test]
+fn perl_fixture()
+{
+    /* neither the POD block nor the variables named like keywords count */
+    assert_fixture("sample.pl", 5.0, 2, 19);
+}
+
+#[test]
+fn haskell_fixture()
+{
+    /* the otherwise guard, the type signatures and the lambda don't count */
+    assert_fixture("sample.hs", 5.0, 3, 17);
+}
+
+#[test]
+fn haskell_constructors_are_not_guards()
+{
+    /* the guard, the three case alternatives and the if in the lambda. the
+     * constructors of the data declaration and the lambda's arrow don't
+     * count */
+    assert_fixture("guards.hs", 5.0, 2, 13);
+}
+
+#[test]
+fn nim_fixture()
+{
+    /* the block comment, the identifiers containing or and not, and the
+     * object of don't count */
+    assert_fixture("sample.nim", 7.0, 3, 19);
+}
+
+#[test]
+fn sql_fixture()
+{
+    /* each WHEN is a branch but END IF isn't, and neither is the OR of
+     * CREATE OR REPLACE */
+    assert_fixture("sample.sql", 8.0, 2, 27);
+}
+
+#[test]
+fn sql_keywords_match_in_any_case()
+{
+    let source = std::fs::read_to_string(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.sql")).unwrap();
+
+    let upper = file_parser::parse_source("sample.sql", &source, &ParserOptions::default()).unwrap();
+    let lower = file_parser::parse_source("sample.sql", &source.to_lowercase(), &ParserOptions::default()).unwrap();
+    assert_eq!(upper, lower);
+    assert_eq!(file_parser::parse_source("query.sql", "select a from t where b Or c", &ParserOptions::default()).unwrap().0, 1.0);
+}
+
+#[test]
+fn supported_extensions_have_keywords()
+{
+    for extension in SUPPORTED_EXTENSIONS
+    {
+        let file = format!("file.{}", extension);
+
+        assert!(file_parser::is_file_extension_valid(&file), "{} is not valid", file);
+        assert!(file_parser::get_keywords(file_parser::get_language(&file)).is_some(), "{} has no keywords", file);
+    }
+}
+
+#[test]
+fn unsupported_extensions_have_no_keywords()
+{
+    for file in ["file.rs", "file.toml", "file.json", "file.yml", "Makefile"]
+    {
+        assert!(!file_parser::is_file_extension_valid(file), "{} is valid", file);
+        assert!(file_parser::get_keywords(file_parser::get_language(file)).is_none(), "{} has keywords", file);
+    }
+}
+
+#[test]
+fn vue_fixture()
+{
+    /* the && and || in the template don't count */
+    assert_fixture("sample.vue", 2.0, 1, 19);
+}
+
+#[test]
+fn svelte_fixture()
+{
+    /* nor do the ones in the markup after the script */
+    assert_fixture("sample.svelte", 3.0, 1, 15);
+}
+
+#[test]
+fn returns_beyond_the_first_are_counted()
+{
+    let options = ParserOptions { count_returns: true, ..Default::default() };
+    let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    /* sign and parse each return twice, count only once */
+    let file = cyclo::analyze_file(&fixtures.join("sample.py"), &options).unwrap();
+    assert_eq!(file.cc, 7.0);
+
+    /* sign returns three times */
+    let file = cyclo::analyze_file(&fixtures.join("sample.c"), &options).unwrap();
+    assert_eq!(file.cc, 6.0);
+}
+
+#[test]
+fn nested_switch_counts_each_case()
+{
+    /* five case labels, with 1 and 2 sharing a line and 'a' in the nested
+     * switch. the defaults and the cases in the string and comment don't
+     * count */
+    assert_fixture("switch.c", 5.0, 1, 22);
+}
+
+#[test]
+fn markdown_fenced_blocks_are_analyzed_by_language()
+{
+    let options = ParserOptions { include_markdown: true, ..Default::default() };
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.md");
+
+    /* the python, c, and js blocks are summed. the prose, the indented code,
+     * the rust block, and the block with no language don't count */
+    let file = cyclo::analyze_file(&path, &options).unwrap();
+    assert_eq!(file.cc, 4.0);
+    assert_eq!(file.functions, 3);
+    assert_eq!(file.nloc, 17);
+
+    /* markdown isn't analyzed unless asked for */
+    assert!(cyclo::analyze_file(&path, &ParserOptions::default()).is_err());
+}
+
+#[test]
+fn stripped_source_drops_comments()
+{
+    let source = "/* a block\n   comment */\nint x; // trailing\nif (x)\n{\n}\n";
+    let stripped = file_parser::stripped_source("file.c", source, &ParserOptions::default()).unwrap();
+
+    /* a line with a comment is dropped whole, like when counting */
+    assert_eq!(stripped, ["if (x)", "{", "}"]);
+    assert!(file_parser::stripped_source("file.rs", source, &ParserOptions::default()).is_none());
+}
+
+#[test]
+fn every_logical_op_on_a_line_is_counted()
+{
+    let source = "int f(int a, int b, int c, int d)\n{\n    if (a && b && c || d)\n    {\n        return 1;\n    }\n    return 0;\n}\n";
+
+    /* the if and the three logical ops */
+    let (cc, _) = file_parser::source_complexity("file.c", source, &ParserOptions::default()).unwrap();
+    assert_eq!(cc, 4.0);
+}
+
+#[test]
+fn module_extensions_are_javascript()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.js");
+    let source = std::fs::read_to_string(path).unwrap();
+
+    for name in ["sample.mjs", "sample.cjs"]
+    {
+        assert_eq!(file_parser::parse_source(name, &source, &ParserOptions::default()), Some((4.0, 3, 22)), "{}", name);
+    }
+}
+
+#[test]
+fn asm_branches_are_counted_when_asked_for()
+{
+    /* only the if without --count-asm, then the jz, jne, and jnz in the asm
+     * blocks. the variables named jmp_count and loop aren't in a block */
+    assert_fixture("asm.c", 1.0, 2, 26);
+
+    let options = ParserOptions { count_asm: true, ..Default::default() };
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/asm.c");
+    assert_eq!(cyclo::analyze_file(&path, &options).unwrap().cc, 4.0);
+}
+
+#[test]
+fn ignored_lines_are_not_counted()
+{
+    /* the if and two elifs between the ignore markers and the one line if
+     * marked to be ignored are dropped, leaving the if, and, and while */
+    assert_fixture("ignore.py", 3.0, 2, 15);
+}
+
+#[test]
+fn shebangs_name_the_language()
+{
+    assert_eq!(file_parser::shebang_extension("#!/usr/bin/env python3"), Some("py"));
+    assert_eq!(file_parser::shebang_extension("#!/usr/bin/python3.11 -u"), Some("py"));
+    assert_eq!(file_parser::shebang_extension("#!/usr/bin/env -S node --no-warnings"), Some("js"));
+    assert_eq!(file_parser::shebang_extension("#! /usr/bin/perl -w"), Some("pl"));
+    assert_eq!(file_parser::shebang_extension("#!/bin/sh"), None);
+    assert_eq!(file_parser::shebang_extension("import sys"), None);
+}
+
+#[test]
+fn extensionless_scripts_are_parsed_by_their_shebang()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tool");
+    assert!(cyclo::analyze_file(&path, &ParserOptions::default()).is_err());
+
+    let options = ParserOptions { detect_shebang: true, ..Default::default() };
+    let file = cyclo::analyze_file(&path, &options).unwrap();
+
+    /* it's counted just like the same script named tool.py */
+    let source = std::fs::read_to_string(&path).unwrap();
+    let (cc, functions, nloc) = file_parser::parse_source("tool.py", &source, &ParserOptions::default()).unwrap();
+    assert_eq!((file.cc, file.functions, file.nloc, file.language.as_str()), (cc, functions, nloc, "py"));
+    assert!(cyclo::walk(path.parent().unwrap(), &options).any(|e| e.path() == path));
+}
+
+#[test]
+fn code_lines_are_counted_without_tokei()
+{
+    /* the lines of only comments and the blank line aren't code, but a line
+     * with code either side of a comment is */
+    let source = "int x; /* opened\n   still a comment\n   closed */ int y;\n// comment\n\n  z(); // trailing\n/* a */ /* b */\n";
+    let keywords = file_parser::get_keywords("c").unwrap();
+    assert_eq!(file_parser::code_lines(source, &keywords), 3);
+
+    /* a line starting with * is only a comment inside a block comment */
+    let source = "/*\n * a comment\n */\nvoid f(int *p)\n{\n    *p = 0;\n}\n";
+    assert_eq!(file_parser::code_lines(source, &keywords), 4);
+
+    /* it agrees with tokei on the fixtures */
+    for name in ["sample.c", "sample.cpp", "sample.js", "sample.zig", "sample.sql"]
+    {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+        let source = std::fs::read_to_string(&path).unwrap();
+        let keywords = file_parser::get_keywords(file_parser::get_language(name)).unwrap();
+
+        assert_eq!(file_parser::code_lines(&source, &keywords), parse_fixture(name).nloc, "{} nloc", name);
+    }
+}
+
+#[test]
+fn listed_scripts_are_parsed_by_their_shebang()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let options = ParserOptions { detect_shebang: true, ..Default::default() };
+
+    /* like the walk, the files changed since a ref include the scripts */
+    let files = cyclo::analyze_paths(&path, &[PathBuf::from("tool")], &options);
+    assert_eq!(files.iter().map(|f| f.label.as_str()).collect::<Vec<_>>(), ["fixtures/tool"]);
+    assert!(cyclo::analyze_paths(&path, &[PathBuf::from("tool")], &ParserOptions::default()).is_empty());
+}
+
+#[test]
+fn binaries_without_a_newline_have_no_shebang()
+{
+    let dir = std::env::temp_dir().join(format!("cyclo-binary-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("blob"), vec![0u8; 1 << 20]).unwrap();
+
+    let options = ParserOptions { detect_shebang: true, ..Default::default() };
+    let name = file_parser::parsed_name(&dir.join("blob"), &options);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(name, "blob");
+}
+
+#[test]
+fn ignore_markers_are_only_read_in_comments()
+{
+    /* the ignored region and the one line function marked to be ignored
+     * add no functions, and the marker in the string is left alone so its
+     * if is counted with the other if and the && */
+    assert_fixture("ignore.c", 3.0, 1, 18);
+}
@@ -0,0 +1,54 @@
+use std::fs;
+use std::sync::Arc;
+
+use cyclo::file_parser::{FileLimit, ParserOptions};
+
+
+/// Options stopping after `max` files
+fn limited(max: usize) -> ParserOptions
+{
+    ParserOptions { max_files: Some(Arc::new(FileLimit::new(max))), ..Default::default() }
+}
+
+#[test]
+fn limit_is_shared_by_every_directory()
+{
+    let root = std::env::temp_dir().join(format!("cyclo-limit-dirs-{}", std::process::id()));
+    let dirs = [root.join("one"), root.join("two")];
+
+    for dir in &dirs
+    {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("a.c"), "int a() { return 0; }\n").unwrap();
+        fs::write(dir.join("b.c"), "int b() { return 1; }\n").unwrap();
+    }
+
+    /* one limit for the whole run, not one for each directory */
+    let options = limited(3);
+    let files = cyclo::analyze_dirs(&root, &dirs, &options, 2);
+    assert_eq!(files.len(), 3);
+    assert!(options.max_files.unwrap().is_truncated());
+
+    /* exactly as many files as the limit isn't truncated */
+    let options = limited(4);
+    let files = cyclo::analyze_dirs(&root, &dirs, &options, 2);
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(files.len(), 4);
+    assert!(!options.max_files.unwrap().is_truncated());
+}
+
+#[test]
+fn failures_dont_count_toward_the_limit()
+{
+    let dir = std::env::temp_dir().join(format!("cyclo-limit-{}", std::process::id()));
+    /* a directory named like a source file is walked but can't be read */
+    fs::create_dir_all(dir.join("a.c")).unwrap();
+    fs::write(dir.join("b.c"), "int main() { return 0; }\n").unwrap();
+
+    let files = cyclo::analyze(&dir, &limited(1), 1);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(files.len(), 1);
+    assert!(files[0].label.ends_with("/b.c"));
+}
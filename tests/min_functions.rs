@@ -0,0 +1,30 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+
+/// Run cyclo on a file for its JSON report with --min-functions
+fn json_report(path: &Path, min_functions: &str) -> Output
+{
+    Command::new(env!("CARGO_BIN_EXE_cyclo")).args(["--format", "json", "--min-functions", min_functions, "--path"])
+                                             .arg(path)
+                                             .output()
+                                             .unwrap()
+}
+
+#[test]
+fn file_without_enough_functions_is_dropped()
+{
+    let dir = std::env::temp_dir().join(format!("cyclo-min-functions-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("script.py"), "if ready:\n    print(1)\n").unwrap();
+
+    let dropped = json_report(&dir.join("script.py"), "1");
+    let kept = json_report(&dir.join("script.py"), "0");
+    fs::remove_dir_all(&dir).unwrap();
+
+    /* a file with no functions has nothing to report */
+    assert!(dropped.status.success());
+    assert!(dropped.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&kept.stdout).contains("\"label\":\"script.py\""));
+}
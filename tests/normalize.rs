@@ -0,0 +1,34 @@
+mod common;
+
+use cyclo::normalize::Normalizer;
+
+use common::file;
+
+
+#[test]
+fn complexities_are_scaled_between_the_least_and_most_complex()
+{
+    let normalizer = Normalizer::new(&[file("a.c", 1, 10.0), file("a.c", 1, 20.0), file("a.c", 1, 50.0)], None);
+
+    assert_eq!(normalizer.apply(10.0), 0.0);
+    assert_eq!(normalizer.apply(20.0), 25.0);
+    assert_eq!(normalizer.apply(50.0), 100.0);
+}
+
+#[test]
+fn reference_max_scales_from_zero_and_caps()
+{
+    let normalizer = Normalizer::new(&[file("a.c", 1, 10.0), file("a.c", 1, 300.0)], Some(200.0));
+
+    assert_eq!(normalizer.apply(10.0), 5.0);
+    assert_eq!(normalizer.apply(300.0), 100.0);
+}
+
+#[test]
+fn equal_complexities_are_zero()
+{
+    let normalizer = Normalizer::new(&[file("a.c", 1, 7.0), file("a.c", 1, 7.0)], None);
+
+    assert_eq!(normalizer.apply(7.0), 0.0);
+    assert_eq!(Normalizer::new(&[], None).apply(7.0), 0.0);
+}
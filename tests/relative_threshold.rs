@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus};
+
+
+/// Run cyclo on a fixture for its JSON report with --relative-threshold
+fn run(path: &str, factor: &str) -> ExitStatus
+{
+    Command::new(env!("CARGO_BIN_EXE_cyclo")).args(["--format", "json", "--relative-threshold", factor, "--path"])
+                                             .arg(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path))
+                                             .output()
+                                             .unwrap()
+                                             .status
+}
+
+#[test]
+fn relative_threshold_is_checked_for_a_single_file()
+{
+    assert!(!run("tests/fixtures/sample.c", "0.5").success());
+    assert!(run("tests/fixtures/sample.c", "1").success());
+}
+
+#[test]
+fn relative_threshold_is_checked_for_a_directory()
+{
+    assert!(!run("tests/fixtures", "0.5").success());
+    assert!(run("tests/fixtures", "1000").success());
+}
@@ -0,0 +1,26 @@
+use std::path::Path;
+
+use cyclo::remote::{self, RemoteClone};
+
+
+#[test]
+fn urls_are_remote()
+{
+    for url in ["https://github.com/user/repo.git", "git@github.com:user/repo.git", "ssh://host/repo", "file:///srv/repo"]
+    {
+        assert!(remote::is_remote(Path::new(url)), "{} isn't remote", url);
+    }
+
+    for path in [".", "src", "/home/user/https", "repo.git"]
+    {
+        assert!(!remote::is_remote(Path::new(path)), "{} is remote", path);
+    }
+}
+
+#[test]
+fn failed_clone_is_an_error()
+{
+    let error = RemoteClone::new("file:///nonexistent/cyclo-test.git").unwrap_err();
+
+    assert!(error.to_string().starts_with("Unable to clone file:///nonexistent/cyclo-test.git"));
+}
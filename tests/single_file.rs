@@ -0,0 +1,43 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+
+/// Run cyclo on the C fixture for its JSON report
+fn run(args: &[&str]) -> Output
+{
+    Command::new(env!("CARGO_BIN_EXE_cyclo")).args(["--format", "json", "--path"])
+                                             .arg(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sample.c"))
+                                             .args(args)
+                                             .output()
+                                             .unwrap()
+}
+
+#[test]
+fn single_file_is_reported_like_a_directory()
+{
+    let cache = std::env::temp_dir().join(format!("cyclo-single-file-{}.cache", std::process::id()));
+    let output = run(&["--top", "1", "--histogram", "--cache", cache.to_str().unwrap()]);
+    let cached = fs::metadata(&cache).is_ok();
+    let _ = fs::remove_file(&cache);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    /* the tables go to stderr, leaving the report alone on stdout */
+    assert!(output.status.success());
+    assert!(stdout.starts_with('{') && stdout.contains("\"label\":\"sample.c\""));
+    assert!(stderr.contains("complexity histogram (1 files)"));
+    assert!(stderr.contains("    sample.c") && !stderr.contains("NaN"));
+    assert!(cached);
+}
+
+#[test]
+fn single_file_filtered_out_is_a_warning()
+{
+    let output = run(&["--min-cc", "1000"]);
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("there are no files left to analyze"));
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::path::PathBuf;
+
+use cyclo::file_parser::{FileResult, ParserOptions};
+use cyclo::output::{self, JsonHeadline, JsonReport};
+
+use common::file;
+
+
+#[test]
+fn headline_is_broken_down_by_language()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let files = cyclo::analyze(&path, &ParserOptions::default(), 1);
+    let headline = JsonHeadline::new(&files);
+
+    assert_eq!(headline.files, files.len() as u64);
+    assert_eq!(headline.nloc, files.iter().map(|f| f.nloc).sum::<u64>());
+    assert_eq!(headline.languages.values().map(|l| l.files).sum::<u64>(), headline.files);
+
+    let c = &headline.languages["c"];
+    let c_files: Vec<&FileResult> = files.iter().filter(|f| f.label.ends_with(".c")).collect();
+    assert_eq!(c.files, c_files.len() as u64);
+    assert_eq!(c.max_cc, c_files.iter().map(|f| f.cc).fold(0.0, f64::max));
+}
+
+#[test]
+fn median_of_an_even_number_of_files_is_the_mean_of_the_middle_two()
+{
+    let headline = JsonHeadline::new(&[file("a.c", 1, 9.0), file("b.c", 1, 1.0), file("c.c", 1, 4.0), file("d.c", 1, 2.0)]);
+    assert_eq!((headline.mean_cc, headline.median_cc, headline.max_cc), (4.0, 3.0, 9.0));
+
+    let headline = JsonHeadline::new(&[]);
+    assert_eq!((headline.files, headline.median_cc, headline.languages.len()), (0, 0.0, 0));
+}
+
+#[test]
+fn pretty_json_is_the_same_report_indented()
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let files = cyclo::analyze(&path, &ParserOptions::default(), 1);
+    let report = JsonReport::new(&files, &[]);
+
+    let compact = output::json(&report, false);
+    let pretty = output::json(&report, true);
+
+    assert!(!compact.contains('\n'));
+    assert!(pretty.contains("\n  \"schema_version\": 1,\n"));
+    assert_eq!(serde_json::from_str::<JsonReport>(&pretty).unwrap(), report);
+    assert_eq!(serde_json::from_str::<JsonHeadline>(&output::summary_json(&files, true)).unwrap(), JsonHeadline::new(&files));
+}
+
+#[test]
+fn output_dir_report_can_be_pretty()
+{
+    let dir = std::env::temp_dir().join(format!("cyclo-output-dir-{}", std::process::id()));
+    let report = JsonReport::new(&[file("src/a.c", 10, 2.0)], &[]);
+
+    output::write_output_dir(&dir, "", &report, false, true).unwrap();
+    let written = std::fs::read_to_string(dir.join("cyclo.json")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(written, output::json(&report, true));
+}
@@ -0,0 +1,42 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::sync::{Arc, Mutex};
+
+use cyclo::file_parser::ParserOptions;
+
+
+#[test]
+fn symlinked_directories_are_followed_once()
+{
+    let tmp = std::env::temp_dir().join(format!("cyclo-symlinks-{}", std::process::id()));
+    let root = tmp.join("root");
+    fs::create_dir_all(tmp.join("outside")).unwrap();
+    fs::create_dir_all(&root).unwrap();
+    fs::write(tmp.join("outside/s.c"), "int s(int x) { return x; }\n").unwrap();
+    fs::write(root.join("a.c"), "int a(int x) { return x; }\n").unwrap();
+
+    /* the shared directory is linked twice, and a link back to the root
+     * would loop forever */
+    symlink("../outside", root.join("shared")).unwrap();
+    symlink("../outside", root.join("copy")).unwrap();
+    symlink(".", root.join("loop")).unwrap();
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let options = ParserOptions { errors: Some(Arc::clone(&errors)), ..Default::default() };
+    let files = cyclo::analyze(&root, &options, 1);
+    fs::remove_dir_all(&tmp).unwrap();
+
+    let mut labels: Vec<&str> = files.iter().map(|f| f.label.as_str()).collect();
+    labels.sort();
+
+    /* the linked file is counted once, under whichever link was walked first */
+    assert_eq!(labels.len(), 2);
+    assert_eq!(labels[0], "root/a.c");
+    assert!(labels[1] == "root/shared/s.c" || labels[1] == "root/copy/s.c");
+
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].kind() == "Walk" && errors[0].file().ends_with("loop"));
+}
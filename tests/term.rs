@@ -0,0 +1,17 @@
+use cyclo::term::{self, Color};
+
+
+#[test]
+fn complexity_is_colored_relative_to_the_mean()
+{
+    assert_eq!(term::complexity_color(25.0, 10.0), Color::Red);
+    assert_eq!(term::complexity_color(20.0, 10.0), Color::Yellow);
+    assert_eq!(term::complexity_color(10.0, 10.0), Color::Green);
+}
+
+#[test]
+fn text_is_only_painted_when_enabled()
+{
+    assert_eq!(term::paint("src/", Color::Dim, true), "\x1b[2msrc/\x1b[0m");
+    assert_eq!(term::paint("src/", Color::Dim, false), "src/");
+}
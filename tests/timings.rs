@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use cyclo::timings;
+
+
+#[test]
+fn run_stats_of_even_number_of_runs()
+{
+    let runs = [4, 1, 3, 2].map(Duration::from_millis);
+    let stats = timings::run_stats(&runs).unwrap();
+
+    assert_eq!(stats.min, Duration::from_millis(1));
+    assert_eq!(stats.max, Duration::from_millis(4));
+    assert_eq!(stats.median, Duration::from_micros(2500));
+    assert_eq!(stats.mean, Duration::from_micros(2500));
+    /* the square root of 1.25ms² */
+    assert_eq!(stats.stddev.as_micros(), 1118);
+}
+
+#[test]
+fn run_stats_of_no_runs()
+{
+    assert!(timings::run_stats(&[]).is_none());
+}
@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use cyclo::file_parser::ParserOptions;
+use cyclo::output::{self, JsonReport};
+use cyclo::treemap::{MeanMode, Treemap};
+use cyclo::validate::{self, ValidateError};
+
+
+/// The treemap of the fixtures
+fn fixtures_treemap() -> Treemap
+{
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    Treemap::from_files(&cyclo::analyze(&path, &ParserOptions::default(), 1))
+}
+
+#[test]
+fn generated_output_is_valid()
+{
+    let treemap = fixtures_treemap();
+    let js = output::js(&treemap, Some("a \"quoted\" [root]"), &[], MeanMode::Simple, false);
+
+    assert!(validate::validate_js(&js).is_ok());
+    assert!(validate::validate_json(&output::json(&JsonReport::new(&[], &[]), true)).is_ok());
+}
+
+#[test]
+fn broken_treemaps_are_caught()
+{
+    /* a parent which isn't a node */
+    let mut treemap = fixtures_treemap();
+    treemap.push("elsewhere/file.c".to_string(), "elsewhere".to_string(), 1, 1.0);
+    let js = output::js(&treemap, None, &[], MeanMode::Simple, false);
+    assert!(matches!(validate::validate_js(&js), Err(ValidateError::MissingParent { .. })));
+
+    /* a node twice */
+    let mut treemap = fixtures_treemap();
+    treemap.push(treemap.labels[0].clone(), treemap.parents[0].clone(), 1, 1.0);
+    let js = output::js(&treemap, None, &[], MeanMode::Simple, false);
+    assert!(matches!(validate::validate_js(&js), Err(ValidateError::DuplicateId { .. })));
+
+    /* an array short of a value */
+    let js = output::js(&fixtures_treemap(), None, &[], MeanMode::Simple, false).replacen("values: [", "values: [1, ", 1);
+    assert!(matches!(validate::validate_js(&js), Err(ValidateError::LengthMismatch { .. })));
+
+    /* an array which isn't JSON, like a label Rust escaped for JS */
+    let js = output::js(&fixtures_treemap(), None, &[], MeanMode::Simple, false).replacen("ids: [\"", "ids: [\"\\u{1}", 1);
+    assert!(matches!(validate::validate_js(&js), Err(ValidateError::InvalidArray { .. })));
+
+    assert!(matches!(validate::validate_js("var jsondata = []"), Err(ValidateError::MissingArray { .. })));
+    assert!(validate::validate_json("{\"files\": []}").is_err());
+}
+
+#[test]
+fn module_exports_the_data()
+{
+    let js = output::js(&fixtures_treemap(), None, &[], MeanMode::Simple, false);
+    let module = output::js_module(&js);
+
+    assert!(module.contains("\nexport const jsondata = [{") && module.contains("\nexport const snippets = []"));
+    assert!(!module.contains("var "));
+    assert!(validate::validate_js(&module).is_ok());
+
+    /* broken snippets are still caught */
+    let module = module.replace("export const snippets = []", "export const snippets = [");
+    assert!(matches!(validate::validate_js(&module), Err(ValidateError::InvalidSnippets { .. })));
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use cyclo::file_parser::{FileResult, ParserOptions};
+use cyclo::treemap::{DirRollup, Treemap};
+use cyclo::watch;
+
+use common::file;
+
+
+/// The nodes of a treemap in label order, so treemaps built in a different
+/// order can be compared
+fn nodes(treemap: &Treemap) -> Vec<(String, String, u64, f64, String)>
+{
+    treemap.check();
+
+    let mut nodes: Vec<(String, String, u64, f64, String)> = (0..treemap.len()).map(|i| (treemap.labels[i].clone(), treemap.parents[i].clone(),
+                                                                                      treemap.nlocs[i], treemap.ccs[i], treemap.languages[i].clone()))
+                                                                                .collect();
+    nodes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    nodes
+}
+
+/// Build a treemap from scratch with the directory rollups
+fn rebuilt(files: &[FileResult], rollup: DirRollup) -> Treemap
+{
+    let mut treemap = Treemap::from_files(files);
+    treemap.rollup(rollup);
+
+    treemap
+}
+
+#[test]
+fn updates_match_a_rebuild()
+{
+    let files = [file("root/a.c", 10, 1.0), file("root/lib/b.c", 20, 2.0), file("root/lib/deep/c.c", 30, 6.0)];
+
+    for rollup in [DirRollup::None, DirRollup::Mean, DirRollup::Max, DirRollup::Sum]
+    {
+        let mut treemap = rebuilt(&files, rollup);
+
+        /* a changed file, then a new file in a new directory */
+        treemap.update_file(&file("root/lib/b.c", 25, 9.0), rollup);
+        treemap.update_file(&file("root/new/sub/d.c", 5, 4.0), rollup);
+
+        let expected = [file("root/a.c", 10, 1.0), file("root/lib/b.c", 25, 9.0), file("root/lib/deep/c.c", 30, 6.0), file("root/new/sub/d.c", 5, 4.0)];
+        assert_eq!(nodes(&treemap), nodes(&rebuilt(&expected, rollup)), "{:?}", rollup);
+
+        /* removing the only file in a directory removes the directory too */
+        treemap.remove_file("root/lib/deep/c.c", rollup);
+        treemap.remove_file("root/new/sub/d.c", rollup);
+
+        let expected = [file("root/a.c", 10, 1.0), file("root/lib/b.c", 25, 9.0)];
+        assert_eq!(nodes(&treemap), nodes(&rebuilt(&expected, rollup)), "{:?}", rollup);
+    }
+}
+
+#[test]
+fn changes_are_found_from_the_modification_times()
+{
+    let time = |secs: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+    let before: HashMap<PathBuf, SystemTime> = [("a.c", 1), ("b.c", 1), ("c.c", 1)].iter().map(|(p, t)| (PathBuf::from(p), time(*t))).collect();
+    let after: HashMap<PathBuf, SystemTime> = [("a.c", 1), ("c.c", 2), ("d.c", 1)].iter().map(|(p, t)| (PathBuf::from(p), time(*t))).collect();
+
+    let changes = watch::changes(&before, &after);
+
+    assert_eq!(changes.changed, [PathBuf::from("c.c"), PathBuf::from("d.c")]);
+    assert_eq!(changes.removed, [PathBuf::from("b.c")]);
+    assert!(watch::changes(&after, &after).is_empty());
+}
+
+#[test]
+fn files_without_enough_functions_are_removed()
+{
+    let mut files = vec![file("root/a.c", 10, 1.0), file("root/b.c", 20, 2.0)];
+    let mut treemap = rebuilt(&files, DirRollup::Mean);
+
+    /* b.c lost its functions, and the new c.c never had any */
+    let b = FileResult { functions: 0, ..file("root/b.c", 15, 1.0) };
+    let c = FileResult { functions: 0, ..file("root/c.c", 5, 1.0) };
+    watch::update(&mut files, &mut treemap, b, Some(1), DirRollup::Mean);
+    watch::update(&mut files, &mut treemap, c, Some(1), DirRollup::Mean);
+
+    let expected = [file("root/a.c", 10, 1.0)];
+    assert_eq!(files.iter().map(|f| f.label.as_str()).collect::<Vec<_>>(), ["root/a.c"]);
+    assert_eq!(nodes(&treemap), nodes(&rebuilt(&expected, DirRollup::Mean)));
+
+    /* without a minimum they're kept */
+    let c = FileResult { functions: 0, ..file("root/c.c", 5, 1.0) };
+    watch::update(&mut files, &mut treemap, c, None, DirRollup::Mean);
+    assert_eq!(files.len(), 2);
+}
+
+#[test]
+fn refresh_finds_the_changed_new_and_removed_files()
+{
+    let root = std::env::temp_dir().join(format!("cyclo-watch-{}", std::process::id()));
+    fs::create_dir_all(root.join("lib")).unwrap();
+    fs::write(root.join("a.c"), "int a() { return 0; }\n").unwrap();
+    fs::write(root.join("lib/b.c"), "int b() { return 0; }\n").unwrap();
+
+    let options = ParserOptions::default();
+    let snapshot = watch::Snapshot::new(&root, &options);
+
+    /* the time is set since a quick write could leave it as it was */
+    File::options().write(true).open(root.join("a.c")).unwrap().set_modified(SystemTime::UNIX_EPOCH).unwrap();
+    fs::remove_file(root.join("lib/b.c")).unwrap();
+    fs::write(root.join("lib/c.c"), "int c() { return 0; }\n").unwrap();
+    fs::create_dir_all(root.join("new/deep")).unwrap();
+    fs::write(root.join("new/deep/d.c"), "int d() { return 0; }\n").unwrap();
+    fs::write(root.join("notes.txt"), "not code\n").unwrap();
+
+    let next = snapshot.refresh(&root, &options);
+    let changes = watch::changes(&snapshot.files, &next.files);
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(changes.changed, [root.join("a.c"), root.join("lib/c.c"), root.join("new/deep/d.c")]);
+    assert_eq!(changes.removed, [root.join("lib/b.c")]);
+}
@@ -0,0 +1,174 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+
+/// The webserver running on a free port, killed when dropped
+struct Server
+{
+    child: Child,
+    addr: String,
+}
+
+impl Server
+{
+    /// Start the webserver serving a file in tests/fixtures as the data
+    fn start(data: &str) -> Server
+    {
+        Server::start_with(data, &[])
+    }
+
+    /// Start the webserver with extra arguments
+    fn start_with(data: &str, args: &[&str]) -> Server
+    {
+        let data = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(data);
+        let mut command = Command::new(env!("CARGO_BIN_EXE_webserver"));
+        command.args(["--port", "0", "--data"]).arg(data).args(args);
+
+        Server::spawn(command)
+    }
+
+    /// Start the webserver in tests/fixtures, where there is no dashboard
+    fn start_without_dashboard() -> Server
+    {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_webserver"));
+        command.args(["--port", "0"]).current_dir(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"));
+
+        Server::spawn(command)
+    }
+
+    /// Spawn the webserver, waiting until it is listening
+    fn spawn(mut command: Command) -> Server
+    {
+        let mut child = command.stdout(Stdio::piped())
+                               .stderr(Stdio::null())
+                               .spawn()
+                               .unwrap();
+
+        /* the address the OS picked is printed once the server is listening */
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+        let addr = line.trim().rsplit(' ').next().unwrap().to_string();
+
+        Server { child, addr }
+    }
+
+    /// Send a GET request, returning the raw response
+    fn get(&self, uri: &str) -> Vec<u8>
+    {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", uri).unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+
+        response
+    }
+}
+
+impl Drop for Server
+{
+    fn drop(&mut self)
+    {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Split a response into its header block and body at the first blank line
+fn split_response(response: &[u8]) -> (String, &[u8])
+{
+    let end = response.windows(4).position(|w| w == b"\r\n\r\n").expect("header block isn't terminated");
+
+    (String::from_utf8(response[..end].to_vec()).unwrap(), &response[end + 4..])
+}
+
+#[test]
+fn non_ascii_file_is_served_in_full()
+{
+    let server = Server::start("unicode.json");
+    let expected = std::fs::read(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/unicode.json")).unwrap();
+
+    let response = server.get("/api/complexity");
+    let (head, body) = split_response(&response);
+
+    /* the length is in bytes, which is more than the number of characters */
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(head.contains(&format!("Content-Length: {}", expected.len())));
+    assert!(expected.len() > String::from_utf8_lossy(&expected).chars().count());
+    assert_eq!(body, expected);
+}
+
+#[test]
+fn health_check_has_exact_body()
+{
+    let server = Server::start("unicode.json");
+    let response = server.get("/healthz");
+    let (head, body) = split_response(&response);
+
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(head.contains("Content-Length: 2"));
+    assert_eq!(body, b"ok");
+}
+
+#[test]
+fn idle_connection_is_dropped_after_timeout()
+{
+    let server = Server::start_with("unicode.json", &["--timeout", "1"]);
+    let mut stream = TcpStream::connect(&server.addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+
+    /* the server closes the connection without a response */
+    let start = Instant::now();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+
+    assert!(response.is_empty());
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn missing_dashboard_serves_a_hint()
+{
+    let server = Server::start_without_dashboard();
+    let response = server.get("/");
+    let (head, body) = split_response(&response);
+
+    assert!(head.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(head.contains("Content-Type: text/html"));
+    assert!(String::from_utf8_lossy(body).contains("cyclo -p &lt;path&gt;"));
+}
+
+#[test]
+fn file_over_the_max_body_size_is_refused()
+{
+    let len = std::fs::metadata(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/unicode.json")).unwrap().len();
+
+    let server = Server::start_with("unicode.json", &["--max-body-size", &(len - 1).to_string()]);
+    let response = server.get("/api/complexity");
+    let (head, body) = split_response(&response);
+
+    assert!(head.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    assert_eq!(body, b"Payload Too Large");
+
+    /* a file of exactly the maximum size is still sent */
+    let server = Server::start_with("unicode.json", &["--max-body-size", &len.to_string()]);
+    assert!(server.get("/api/complexity").starts_with(b"HTTP/1.1 200 OK\r\n"));
+}
+
+#[test]
+fn port_in_use_falls_back_to_a_free_one()
+{
+    let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = taken.local_addr().unwrap().port();
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_webserver"));
+    command.args(["--port", &port.to_string()]).current_dir(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"));
+    let server = Server::spawn(command);
+
+    /* the port printed is the one actually listened on */
+    assert_ne!(server.addr, format!("127.0.0.1:{}", port));
+    assert!(server.get("/healthz").ends_with(b"ok"));
+}